@@ -15,9 +15,56 @@ pub enum FlashLoanError {
     Unathorized,
 }
 
+#[derive(Debug)]
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
 pub enum AdminError {
     AddAssetUnauthorized,
     PushPriceUnauthorized,
+    SetFeedUnauthorized,
+    SetPriceBoundsUnauthorized,
+    ConfirmPriceUnauthorized,
+    ConfirmPriceNotQuarantined,
+    ConfirmPriceSameAuthority,
+    ConfirmPriceOutdated,
+    SetFeedersUnauthorized,
+    SetFeedersInvalid,
+    SubmitPriceUnauthorized,
+    SubmitPriceZero,
     PushParamsUnauthorized,
+    WithdrawReserveUnauthorized,
+    WithdrawReserveFailed(finance2::errors::LAssetError),
+    SkimUnauthorized,
+    SkimFailed(finance2::errors::LAssetError),
+    SyncUnauthorized,
+    SyncFailed(finance2::errors::LAssetError),
+    SetGuardianUnauthorized,
+    PauseUnauthorized,
+    UnpauseUnauthorized,
+    SetPausedFailed(finance2::errors::LAssetError),
+    AddPublisherUnauthorized,
+    RemovePublisherUnauthorized,
+    SetQuorumUnauthorized,
+    PushSignedPriceDisabled,
+    PushSignedPriceInvalidSignature,
+    PushSignedPriceUnknownPublisher,
+    PushSignedPriceDuplicateSigner,
+    PushSignedPriceQuorumNotReached,
+    PushSignedPriceReplayed,
+    PushSignedPriceOutdated,
+    PushSignedPriceFromFuture,
+    PushCategoryUnauthorized,
+    PushCategoryInvalid,
+    SetAssetCategoryUnauthorized,
+    SetUserCategoryNotFound,
+    SetUserCategoryUnhealthy,
+    SetStatusUnauthorized,
+    SetStatusFailed(finance2::errors::LAssetError),
+    RemoveAssetUnauthorized,
+    RemoveAssetFailed(finance2::errors::LAssetError),
+    UpgradeUnauthorized,
+    UpgradeFailed,
+    UpgradePoolFailed(finance2::errors::LAssetError),
+    MigrateUnauthorized,
+    MigrateFromNewerVersion,
+    MigratePoolFailed(finance2::errors::LAssetError),
 }
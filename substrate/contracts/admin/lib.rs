@@ -5,6 +5,7 @@ use ink::primitives::AccountId;
 use ink::prelude::vec::Vec;
 
 mod errors;
+mod structs;
 
 #[ink::trait_definition]
 pub trait FlashLoanReceiver {
@@ -15,26 +16,56 @@ pub trait FlashLoanReceiver {
     fn on_flash_loan(&mut self, initiator: AccountId, token: AccountId, amount: u128, fee: u128, data: Vec<u8>) -> Result<(), FlashLoanReceiverError>;
 }
 
+#[allow(clippy::large_enum_variant)]
 #[ink::contract]
 mod admin {
-    use finance2::logic::require;
+    use finance2::logic::{mulw, require};
+    use finance2::errors::LAssetError;
     use finance2::LAssetContractRef;
-    use finance2::structs::{AssetParams, AssetPool, LAsset, UpdateOrRepayResult, UpdateResult};
+    use finance2::migration::{append_fields, get_appended};
+    use finance2::structs::{AssetParams, AssetPool, AssetStatus, Category, CategoryCheck, Health, LAsset, PauseFlags, PoolHealth, PriceMode, UpdateOrRepayResult, UpdateResult};
     use ink::contract_ref;
+    use ink::env::hash::{HashOutput, Keccak256};
     use ink::prelude::vec::Vec;
-    use ink::storage::Mapping;
+    use ink::scale::Encode;
+    use ink::storage::{Lazy, Mapping};
+    use ink::storage::traits::StorageKey;
     use crate::FlashLoanReceiver;
+    use traits::price_feed::PriceFeed;
     use traits::psp22::PSP22;
     use crate::errors::{AdminError, FlashLoanError};
+    use crate::structs::{Aggregation, FeederStats, Price, PriceBounds, PricePayload, Twap};
+
+    /// Layout version, `migrate` brings older storage up to it
+    pub const STORAGE_VERSION: u32 = 5;
 
     #[ink(storage)]
     pub struct Admin {
+        pub version: u32,
         pub dao: AccountId, // TODO: to be removed, when real dao is implemented
+        pub guardian: AccountId,
         pub hash: Hash,
         pub next: AccountId,
 
-        pub prices: Mapping<AccountId, (u128, u128)>,
+        pub prices: Mapping<AccountId, Price>,
+        pub feeds: Mapping<AccountId, AccountId>,
+        pub price_bounds: Mapping<AccountId, PriceBounds>,
+        pub quarantined: Mapping<AccountId, (Price, AccountId)>,
+        pub twaps: Mapping<AccountId, Twap>,
+        pub feeders: Mapping<AccountId, Vec<AccountId>>,
+        pub aggregations: Mapping<AccountId, Aggregation>,
+        pub submissions: Mapping<(AccountId, AccountId), Price>,
+        pub feeder_stats: Mapping<(AccountId, AccountId), FeederStats>,
+        pub publishers: Mapping<[u8; 33], ()>,
+        pub quorum: Lazy<u32>,
+        pub nonces: Mapping<(AccountId, u64), ()>,
         pub params: Mapping<AccountId, AssetParams>,
+        pub paused: Mapping<AccountId, PauseFlags>,
+        pub status: Mapping<AccountId, AssetStatus>,
+
+        pub categories: Mapping<u32, Category>,
+        pub asset_categories: Mapping<AccountId, u32>,
+        pub user_categories: Mapping<AccountId, u32>,
     }
 
     impl Admin {
@@ -42,12 +73,32 @@ mod admin {
         pub fn new(hash: Hash) -> Self {
             let dao = Self::env().caller();
             let next = Self::env().account_id();
+            let mut quorum = Lazy::new();
+            quorum.set(&0);
             Self {
+                version: STORAGE_VERSION,
                 dao,
+                guardian: dao,
                 hash,
                 next,
                 prices: Mapping::new(),
+                feeds: Mapping::new(),
+                price_bounds: Mapping::new(),
+                quarantined: Mapping::new(),
+                twaps: Mapping::new(),
+                feeders: Mapping::new(),
+                aggregations: Mapping::new(),
+                submissions: Mapping::new(),
+                feeder_stats: Mapping::new(),
+                publishers: Mapping::new(),
+                quorum,
+                nonces: Mapping::new(),
                 params: Mapping::new(),
+                paused: Mapping::new(),
+                status: Mapping::new(),
+                categories: Mapping::new(),
+                asset_categories: Mapping::new(),
+                user_categories: Mapping::new(),
             }
         }
 
@@ -66,25 +117,393 @@ mod admin {
         }
 
         #[ink(message)]
-        pub fn pull_prices(&self) {
+        pub fn add_native_asset(&mut self, gas_collateral: u128, decimals: u8) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::AddAssetUnauthorized)?;
+
+            let salt: [u8; 32] = *self.env().account_id().as_ref();
+            let builder = LAssetContractRef::new_native(self.next, gas_collateral, decimals);
+            let instantiator = builder.salt_bytes(salt).code_hash(self.hash).endowment(0);
+            let contract = instantiator.instantiate();
+            
+            self.next = *contract.as_ref();
+            Ok(())
+        }
+
+        /// Sends each pool the price picked by its `price_mode`, collateral is valued at the first and debt at the second.
+        /// Members of a category with a price source also get the source's price, the older of both updates counts for staleness.
+        /// Aggregated and feed prices pass the asset's bounds first, like pushed ones.
+        #[ink(message)]
+        pub fn pull_prices(&mut self) {
             let this = self.env().account_id();
+            let mut spots = Vec::new();
             let mut current = self.next;
             while current != this {
-                let mut asset: contract_ref!(AssetPool) = current.into();
-                let (price, price_scaler) = self.prices.get(current).unwrap_or((0, 1)); // TODO: use chainlink or switchboard instead
-                current = asset.set_price(price, price_scaler).unwrap(); //impossible to fail
+                let spot = self.spot_price_once(current, &mut spots).unwrap_or(Price {price: 0, price_scaler: 1, updated_at: 0});
+                let (price, debt_price) = self.mode_prices(current, &spot);
+                let source = self.asset_categories.get(current)
+                    .and_then(|category| self.categories.get(category))
+                    .and_then(|category| category.price_source);
+                let source = match source {
+                    Some(source) => self.spot_price_once(source, &mut spots),
+                    None => None,
+                };
+                let (category_price, updated_at) = match source {
+                    Some(source) => {
+                        let category_price = mulw(source.price, spot.price_scaler).div(source.price_scaler).unwrap_or(u128::MAX);
+                        (category_price, spot.updated_at.min(source.updated_at))
+                    },
+                    None => (0, spot.updated_at),
+                };
+                current = price_pool(current, price, debt_price, category_price, spot.price_scaler, updated_at).unwrap(); //impossible to fail
+            }
+        }
+
+        /// `spot_price` of each asset once per `pull_prices`, however many category members share it as a source
+        fn spot_price_once(&mut self, asset: AccountId, spots: &mut Vec<(AccountId, Option<Price>)>) -> Option<Price> {
+            if let Some((_, spot)) = spots.iter().find(|(a, _)| *a == asset) {
+                return spot.clone();
+            }
+            let spot = self.spot_price(asset);
+            spots.push((asset, spot.clone()));
+            spot
+        }
+
+        /// Accepts the feeders' median, else the feed's price, unless older than the stored one, then returns the last accepted price.
+        /// Prices out of bounds get quarantined, with Admin itself as the pusher so either authority may confirm them.
+        pub(crate) fn spot_price(&mut self, asset: AccountId) -> Option<Price> {
+            if let Some(price) = self.median_price(asset).or_else(|| self.feed_price(asset)) {
+                // a lagging source must not move `updated_at` back past a newer pushed price
+                let last = self.price(asset);
+                let updated_at = last.as_ref().map(|p| p.updated_at).unwrap_or(0);
+                // nor get observed again when nothing changed
+                if price.updated_at >= updated_at && last.as_ref() != Some(&price) {
+                    let this = self.env().account_id();
+                    // a pool that fails to pause keeps the price quarantined, the other pools still get theirs
+                    let _ = self.accept_price(asset, price, this);
+                }
+            }
+            self.price(asset)
+        }
+
+        /// Stored price of `asset`, also in the layout before `updated_at`, `migrate` only rewrites the entries of pools in the chain
+        fn price(&self, asset: AccountId) -> Option<Price> {
+            get_appended(&(self.prices.key(), asset), &[0u64.encode()])
+        }
+
+        /// Stored params of `asset`, also ones written before the later fields were appended
+        pub(crate) fn params(&self, asset: AccountId) -> AssetParams {
+            let appended = [(false, 0u128).encode(), 0u128.encode(), (PriceMode::Spot, 0u128).encode()];
+            get_appended(&(self.params.key(), asset), &appended).unwrap_or_default()
+        }
+
+        /// Adds the time the previous price was in force to the cumulative price, then records `price`
+        fn observe(&mut self, asset: AccountId, price: &Price) {
+            let now = self.env().block_timestamp();
+            let Some(mut twap) = self.twaps.get(asset) else {
+                let twap = Twap {
+                    price_scaler: price.price_scaler,
+                    price: price.price,
+                    cumulative: 0,
+                    updated_at: now,
+                    checkpoint_cumulative: 0,
+                    checkpoint_at: now,
+                    pending_cumulative: 0,
+                    pending_at: now,
+                };
+                self.twaps.insert(asset, &twap);
+                return;
+            };
+            let elapsed = now.saturating_sub(twap.updated_at) as u128;
+            twap.cumulative = twap.cumulative.wrapping_add(twap.price.wrapping_mul(elapsed));
+            twap.price = mulw(price.price, twap.price_scaler).div(price.price_scaler).unwrap_or(u128::MAX);
+            twap.updated_at = now;
+
+            let window = self.params(asset).twap_window;
+            if now.saturating_sub(twap.pending_at) as u128 >= window {
+                twap.checkpoint_cumulative = twap.pending_cumulative;
+                twap.checkpoint_at = twap.pending_at;
+                twap.pending_cumulative = twap.cumulative;
+                twap.pending_at = now;
+            }
+            self.twaps.insert(asset, &twap);
+        }
+
+        /// Collateral and debt prices by the asset's `price_mode`, spot ones until a TWAP is available
+        pub(crate) fn mode_prices(&self, asset: AccountId, spot: &Price) -> (u128, u128) {
+            let twap = self.twap_price(asset, spot.price_scaler);
+            match (self.params(asset).price_mode, twap) {
+                (PriceMode::Twap, Some(twap)) => (twap, twap),
+                (PriceMode::Conservative, Some(twap)) => (spot.price.min(twap), spot.price.max(twap)),
+                _ => (spot.price, spot.price),
+            }
+        }
+
+        /// Average price since the checkpoint, `None` before any time has passed since the first observation
+        #[ink(message)]
+        pub fn twap(&self, asset: AccountId) -> Option<Price> {
+            let twap = self.twaps.get(asset)?;
+            let now = self.env().block_timestamp();
+            let elapsed = now.saturating_sub(twap.checkpoint_at) as u128;
+            if elapsed == 0 {
+                return None;
+            }
+            let pending = now.saturating_sub(twap.updated_at) as u128;
+            let cumulative = twap.cumulative.wrapping_add(twap.price.wrapping_mul(pending));
+            let price = cumulative.wrapping_sub(twap.checkpoint_cumulative) / elapsed;
+            Some(Price {price, price_scaler: twap.price_scaler, updated_at: twap.updated_at})
+        }
+
+        fn twap_price(&self, asset: AccountId, price_scaler: u128) -> Option<u128> {
+            let twap = self.twap(asset)?;
+            mulw(twap.price, price_scaler).div(twap.price_scaler)
+        }
+
+        /// A feed that errors or traps falls back to the pushed price, which then goes stale in the pools
+        fn feed_price(&self, asset: AccountId) -> Option<Price> {
+            use ink::codegen::TraitCallBuilder;
+            let feed: contract_ref!(PriceFeed) = self.feeds.get(asset)?.into();
+            match feed.call().latest_price(asset).try_invoke() {
+                Ok(Ok(Ok((price, price_scaler, updated_at)))) => Some(Price {price, price_scaler, updated_at}),
+                _ => None,
+            }
+        }
+
+        /// Median of the fresh submissions, `None` while there are fewer than required
+        pub(crate) fn median_price(&self, asset: AccountId) -> Option<Price> {
+            let aggregation = self.aggregations.get(asset)?;
+            let (mut prices, updated_at) = self.fresh_submissions(asset, &aggregation, None);
+            if prices.is_empty() || prices.len() < aggregation.min_submissions as usize {
+                return None;
+            }
+            Some(Price {price: median(&mut prices), price_scaler: aggregation.price_scaler, updated_at})
+        }
+
+        /// Prices submitted within `max_age` by the asset's feeders other than `skip`, with the oldest submission time
+        fn fresh_submissions(&self, asset: AccountId, aggregation: &Aggregation, skip: Option<AccountId>) -> (Vec<u128>, Timestamp) {
+            let now = self.env().block_timestamp();
+            let mut prices = Vec::new();
+            let mut updated_at = now;
+            for feeder in self.feeders.get(asset).unwrap_or_default() {
+                if Some(feeder) == skip {
+                    continue;
+                }
+                if let Some(submission) = self.submissions.get((asset, feeder)) {
+                    if now.saturating_sub(submission.updated_at) <= aggregation.max_age {
+                        prices.push(submission.price);
+                        updated_at = updated_at.min(submission.updated_at);
+                    }
+                }
+            }
+            (prices, updated_at)
+        }
+
+        /// Replaces the feeders of `asset`, an empty list turns aggregation off. Each feeder may be listed once
+        #[ink(message)]
+        pub fn set_feeders(&mut self, asset: AccountId, feeders: Vec<AccountId>, aggregation: Aggregation) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SetFeedersUnauthorized)?;
+
+            if feeders.is_empty() {
+                self.feeders.remove(asset);
+                self.aggregations.remove(asset);
+            } else {
+                require(aggregation.min_submissions != 0 && aggregation.price_scaler != 0, AdminError::SetFeedersInvalid)?;
+                let distinct = feeders.iter().enumerate().all(|(i, feeder)| !feeders[..i].contains(feeder));
+                require(distinct, AdminError::SetFeedersInvalid)?;
+                self.feeders.insert(asset, &feeders);
+                self.aggregations.insert(asset, &aggregation);
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn submit_price(&mut self, asset: AccountId, price: u128) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            let feeders = self.feeders.get(asset).unwrap_or_default();
+            require(feeders.contains(&caller), AdminError::SubmitPriceUnauthorized)?;
+            let aggregation = self.aggregations.get(asset).ok_or(AdminError::SubmitPriceUnauthorized)?;
+            require(price != 0, AdminError::SubmitPriceZero)?;
+
+            let mut stats = self.feeder_stats.get((asset, caller)).unwrap_or_default();
+            stats.submissions = stats.submissions.saturating_add(1);
+            let (mut others, _) = self.fresh_submissions(asset, &aggregation, Some(caller));
+            if !others.is_empty() {
+                let reference = median(&mut others);
+                let deviation = mulw(price.abs_diff(reference), u128::MAX).div(reference).unwrap_or(u128::MAX);
+                stats.compared = stats.compared.saturating_add(1);
+                stats.last_deviation = deviation;
+                stats.max_deviation = stats.max_deviation.max(deviation);
+                stats.total_deviation = stats.total_deviation.saturating_add(deviation);
+            }
+            self.feeder_stats.insert((asset, caller), &stats);
+
+            let updated_at = self.env().block_timestamp();
+            self.submissions.insert((asset, caller), &Price {price, price_scaler: aggregation.price_scaler, updated_at});
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn feeder_stats(&self, asset: AccountId, feeder: AccountId) -> FeederStats {
+            self.feeder_stats.get((asset, feeder)).unwrap_or_default()
+        }
+
+        /// Reads the asset's price from `feed` from now on, `None` goes back to pushed prices
+        #[ink(message)]
+        pub fn set_feed(&mut self, asset: AccountId, feed: Option<AccountId>) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SetFeedUnauthorized)?;
+
+            if let Some(feed) = feed {
+                self.feeds.insert(asset, &feed);
+            } else {
+                self.feeds.remove(asset);
             }
+            Ok(())
         }
 
         #[ink(message)]
         pub fn push_price(&mut self, asset: AccountId, price: u128, price_scaler: u128) -> Result<(), AdminError> {
             let caller = self.env().caller();
-            require(caller == self.dao, AdminError::PushPriceUnauthorized)?; // TODO: use pyth payload instead
+            require(caller == self.dao, AdminError::PushPriceUnauthorized)?;
+
+            let updated_at = self.env().block_timestamp();
+            self.accept_price(asset, Price {price, price_scaler, updated_at}, caller)
+        }
+
+        /// Applies a price, or quarantines it until an authority other than `pusher` confirms it when it breaks the asset's bounds
+        fn accept_price(&mut self, asset: AccountId, price: Price, pusher: AccountId) -> Result<(), AdminError> {
+            if let Some(bounds) = self.price_bounds.get(asset) {
+                if !self.within_bounds(asset, &bounds, &price) {
+                    self.quarantined.insert(asset, &(price, pusher));
+                    if bounds.auto_pause && self.is_pool(asset) {
+                        let flags = PauseFlags {borrow: true, liquidate: true, ..PauseFlags::default()};
+                        let paused = self.paused.get(asset).unwrap_or_default().union(&flags);
+                        self.set_paused(asset, paused)?;
+                    }
+                    return Ok(());
+                }
+            }
+            self.observe(asset, &price);
+            self.prices.insert(asset, &price);
+            Ok(())
+        }
+
+        fn within_bounds(&self, asset: AccountId, bounds: &PriceBounds, price: &Price) -> bool {
+            let Some(normalized) = mulw(price.price, bounds.price_scaler).div(price.price_scaler) else {
+                return false;
+            };
+            if normalized < bounds.min_price || normalized > bounds.max_price {
+                return false;
+            }
+            match self.price(asset) {
+                Some(last) if price.updated_at.saturating_sub(last.updated_at) <= bounds.window => {
+                    match mulw(last.price, bounds.price_scaler).div(last.price_scaler) {
+                        Some(last_normalized) if last_normalized != 0 => {
+                            let change = mulw(normalized.abs_diff(last_normalized), u128::MAX).div(last_normalized);
+                            change.is_some_and(|c| c <= bounds.max_change)
+                        },
+                        _ => true,
+                    }
+                },
+                _ => true,
+            }
+        }
+
+        #[ink(message)]
+        pub fn set_price_bounds(&mut self, asset: AccountId, bounds: Option<PriceBounds>) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SetPriceBoundsUnauthorized)?;
+
+            if let Some(bounds) = bounds {
+                self.price_bounds.insert(asset, &bounds);
+            } else {
+                self.price_bounds.remove(asset);
+            }
+            Ok(())
+        }
+
+        /// Applies the quarantined price, the dao and the guardian have to confirm each other's pushes
+        #[ink(message)]
+        pub fn confirm_price(&mut self, asset: AccountId) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao || caller == self.guardian, AdminError::ConfirmPriceUnauthorized)?;
+
+            let (price, pusher) = self.quarantined.get(asset).ok_or(AdminError::ConfirmPriceNotQuarantined)?;
+            require(caller != pusher, AdminError::ConfirmPriceSameAuthority)?;
+            let updated_at = self.price(asset).map(|p| p.updated_at).unwrap_or(0);
+            require(price.updated_at >= updated_at, AdminError::ConfirmPriceOutdated)?;
+
+            self.quarantined.remove(asset);
+            self.observe(asset, &price);
+            self.prices.insert(asset, &price);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn quarantined_price(&self, asset: AccountId) -> Option<Price> {
+            self.quarantined.get(asset).map(|(price, _)| price)
+        }
+
+        #[ink(message)]
+        pub fn add_publisher(&mut self, publisher: [u8; 33]) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::AddPublisherUnauthorized)?;
+
+            self.publishers.insert(publisher, &());
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn remove_publisher(&mut self, publisher: [u8; 33]) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::RemovePublisherUnauthorized)?;
+
+            self.publishers.remove(publisher);
+            Ok(())
+        }
+
+        /// Number of distinct publishers that have to sign a payload, zero disables signed prices
+        #[ink(message)]
+        pub fn set_quorum(&mut self, quorum: u32) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SetQuorumUnauthorized)?;
 
-            self.prices.insert(asset, &(price, price_scaler));
+            self.quorum.set(&quorum);
             Ok(())
         }
 
+        /// Accepts a price signed by a quorum of publishers (65 bytes secp256k1 recoverable signatures over
+        /// the keccak256 of the scale encoded admin address and payload), so the caller does not need to be trusted
+        #[ink(message)]
+        pub fn push_signed_price(&mut self, payload: PricePayload, signatures: Vec<[u8; 65]>) -> Result<(), AdminError> {
+            let quorum = self.quorum.get().unwrap_or(0);
+            require(quorum != 0, AdminError::PushSignedPriceDisabled)?;
+
+            let asset = payload.asset;
+            require(!self.nonces.contains((asset, payload.nonce)), AdminError::PushSignedPriceReplayed)?;
+            require(payload.timestamp <= self.env().block_timestamp(), AdminError::PushSignedPriceFromFuture)?;
+            let updated_at = self.price(asset).map(|p| p.updated_at).unwrap_or(0);
+            require(payload.timestamp >= updated_at, AdminError::PushSignedPriceOutdated)?;
+
+            let this = self.env().account_id();
+            let mut hash = <Keccak256 as HashOutput>::Type::default();
+            ink::env::hash_encoded::<Keccak256, _>(&(this, &payload), &mut hash);
+
+            let mut signers: Vec<[u8; 33]> = Vec::new();
+            for signature in signatures.iter() {
+                let signer = self.env().ecdsa_recover(signature, &hash).map_err(|_| AdminError::PushSignedPriceInvalidSignature)?;
+                require(self.publishers.contains(signer), AdminError::PushSignedPriceUnknownPublisher)?;
+                require(!signers.contains(&signer), AdminError::PushSignedPriceDuplicateSigner)?;
+                signers.push(signer);
+            }
+            require(signers.len() >= quorum as usize, AdminError::PushSignedPriceQuorumNotReached)?;
+
+            self.nonces.insert((asset, payload.nonce), &());
+            let caller = self.env().caller();
+            self.accept_price(asset, Price {price: payload.price, price_scaler: payload.price_scaler, updated_at: payload.timestamp}, caller)
+        }
+
         #[ink(message)]
         pub fn push_params(&mut self, asset: AccountId, params: AssetParams) -> Result<(), AdminError> {
             let caller = self.env().caller();
@@ -100,11 +519,298 @@ mod admin {
             let mut current = self.next;
             while current != this {
                 let mut asset: contract_ref!(AssetPool) = current.into();
-                let params = self.params.get(current).unwrap_or_default();
+                let params = self.params(current);
                 current = asset.set_params(params).unwrap(); //impossible to fail
             }
         }
 
+        #[ink(message)]
+        pub fn push_category(&mut self, category: u32, params: Category) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::PushCategoryUnauthorized)?;
+            require(category != 0, AdminError::PushCategoryInvalid)?; // zero stands for no category
+
+            self.categories.insert(category, &params);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_asset_category(&mut self, asset: AccountId, category: u32) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SetAssetCategoryUnauthorized)?;
+
+            self.asset_categories.insert(asset, &category);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pull_categories(&self) {
+            let this = self.env().account_id();
+            let mut current = self.next;
+            while current != this {
+                let mut asset: contract_ref!(AssetPool) = current.into();
+                let category = self.asset_categories.get(current).unwrap_or(0);
+                let params = self.categories.get(category).unwrap_or_default();
+                current = asset.set_category(category, params).unwrap(); //impossible to fail
+            }
+        }
+
+        /// Opts the caller into `category`, zero opts out. The account has to stay healthy under the new valuation.
+        #[ink(message)]
+        pub fn set_user_category(&mut self, category: u32) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(category == 0 || self.categories.contains(category), AdminError::SetUserCategoryNotFound)?;
+
+            self.user_categories.insert(caller, &category);
+
+            let health = self.health(caller);
+            let is_healthy = health.total_initial_debt_value == 0 || health.total_initial_collateral_value > health.total_initial_debt_value;
+            require(is_healthy, AdminError::SetUserCategoryUnhealthy)
+        }
+
+        #[ink(message)]
+        pub fn upgrade(&mut self, code_hash: Hash) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::UpgradeUnauthorized)?;
+
+            self.env().set_code_hash(&code_hash).map_err(|_| AdminError::UpgradeFailed)
+        }
+
+        #[ink(message)]
+        pub fn upgrade_pools(&mut self, code_hash: Hash) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::UpgradeUnauthorized)?;
+
+            let this = self.env().account_id();
+            let mut current = self.next;
+            while current != this {
+                let mut asset: contract_ref!(AssetPool) = current.into();
+                current = asset.upgrade(code_hash).map_err(AdminError::UpgradePoolFailed)?;
+            }
+            self.hash = code_hash;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn migrate(&mut self) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::MigrateUnauthorized)?;
+            require(self.version <= STORAGE_VERSION, AdminError::MigrateFromNewerVersion)?;
+
+            for version in self.version..STORAGE_VERSION {
+                self.migrate_from(version);
+            }
+            self.version = STORAGE_VERSION;
+            Ok(())
+        }
+
+        /// Rewrites what `version` stored, see `STORAGE_VERSION`
+        fn migrate_from(&mut self, version: u32) {
+            match version {
+                1 => { // isolated and isolated_debt_ceiling were appended to AssetParams
+                    for asset in self.assets() {
+                        append_fields(&(self.params.key(), asset), &(false, 0u128));
+                    }
+                },
+                2 => { // prices were (price, price_scaler) without updated_at, max_price_age was appended to AssetParams
+                    for asset in self.assets() {
+                        let key = (self.prices.key(), asset);
+                        if let Ok(Some((price, price_scaler))) = ink::env::get_contract_storage::<_, (u128, u128)>(&key) {
+                            self.prices.insert(asset, &Price {price, price_scaler, updated_at: 0});
+                        }
+                        append_fields(&(self.params.key(), asset), &0u128);
+                    }
+                },
+                3 => { // signed prices were added
+                    self.quorum.set(&0);
+                },
+                4 => { // price_mode and twap_window were appended to AssetParams
+                    for asset in self.assets() {
+                        append_fields(&(self.params.key(), asset), &(PriceMode::Spot, 0u128));
+                    }
+                },
+                _ => {},
+            }
+        }
+
+        /// Pools in the chain, `collateral_of` only reads `next`, so it works on pools that are not migrated yet
+        fn assets(&self) -> Vec<AccountId> {
+            let this = self.env().account_id();
+            let mut assets = Vec::new();
+            let mut current = self.next;
+            while current != this {
+                assets.push(current);
+                current = next_pool(current, this);
+            }
+            assets
+        }
+
+        /// Price sources and other priced accounts need not be pools of this Admin, or contracts at all
+        fn is_pool(&self, asset: AccountId) -> bool {
+            self.assets().contains(&asset)
+        }
+
+        #[ink(message)]
+        pub fn migrate_pools(&mut self) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::MigrateUnauthorized)?;
+
+            let this = self.env().account_id();
+            let mut current = self.next;
+            while current != this {
+                let mut asset: contract_ref!(AssetPool) = current.into();
+                current = asset.migrate().map_err(AdminError::MigratePoolFailed)?;
+            }
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_guardian(&mut self, guardian: AccountId) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SetGuardianUnauthorized)?;
+
+            self.guardian = guardian;
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn pause(&mut self, asset: AccountId, flags: PauseFlags) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.guardian || caller == self.dao, AdminError::PauseUnauthorized)?;
+
+            let paused = self.paused.get(asset).unwrap_or_default().union(&flags);
+            self.set_paused(asset, paused)
+        }
+
+        #[ink(message)]
+        pub fn unpause(&mut self, asset: AccountId, flags: PauseFlags) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::UnpauseUnauthorized)?;
+
+            let paused = self.paused.get(asset).unwrap_or_default().difference(&flags);
+            self.set_paused(asset, paused)
+        }
+
+        fn set_paused(&mut self, asset: AccountId, paused: PauseFlags) -> Result<(), AdminError> {
+            pause_pool(asset, paused.clone()).map_err(AdminError::SetPausedFailed)?;
+            self.paused.insert(asset, &paused);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn set_status(&mut self, asset: AccountId, status: AssetStatus) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SetStatusUnauthorized)?;
+
+            let mut pool: contract_ref!(AssetPool) = asset.into();
+            pool.set_status(status).map_err(AdminError::SetStatusFailed)?;
+            self.status.insert(asset, &status);
+            Ok(())
+        }
+
+        /// Takes an emptied reduce-only pool out of the chain, so traversals no longer pay for it
+        #[ink(message)]
+        pub fn remove_asset(&mut self, asset: AccountId) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::RemoveAssetUnauthorized)?;
+
+            let mut pool: contract_ref!(AssetPool) = asset.into();
+            let next = pool.remove().map_err(AdminError::RemoveAssetFailed)?;
+
+            if self.next == asset {
+                self.next = next;
+            } else {
+                let this = self.env().account_id();
+                let mut current = self.next;
+                while current != this {
+                    let mut pool: contract_ref!(AssetPool) = current.into();
+                    current = pool.relink(asset, next).unwrap(); //impossible to fail
+                }
+            }
+            self.status.insert(asset, &AssetStatus::Removed);
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn withdraw_reserve(&mut self, asset: AccountId, amount: u128, target: AccountId) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::WithdrawReserveUnauthorized)?;
+
+            let mut pool: contract_ref!(AssetPool) = asset.into();
+            pool.withdraw_reserve(amount, target).map_err(AdminError::WithdrawReserveFailed)
+        }
+
+        #[ink(message)]
+        pub fn skim(&mut self, asset: AccountId, target: AccountId) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SkimUnauthorized)?;
+
+            let mut pool: contract_ref!(AssetPool) = asset.into();
+            pool.skim(target).map_err(AdminError::SkimFailed)
+        }
+
+        #[ink(message)]
+        pub fn sync(&mut self, asset: AccountId) -> Result<(), AdminError> {
+            let caller = self.env().caller();
+            require(caller == self.dao, AdminError::SyncUnauthorized)?;
+
+            let mut pool: contract_ref!(AssetPool) = asset.into();
+            pool.sync().map_err(AdminError::SyncFailed)
+        }
+
+        #[ink(message)]
+        pub fn reserves(&self) -> Vec<(AccountId, u128)> {
+            let this = self.env().account_id();
+            let mut reserves = Vec::new();
+            let mut current = self.next;
+            while current != this {
+                let asset: contract_ref!(AssetPool) = current.into();
+                let (next, reserve) = asset.reserve();
+                reserves.push((current, reserve));
+                current = next;
+            }
+            reserves
+        }
+
+        #[ink(message)]
+        pub fn health(&self, user: AccountId) -> Health {
+            let this = self.env().account_id();
+            let mut health = Health::default();
+            let mut category_health = Health::default();
+            let mut categories = CategoryCheck::default();
+            categories.user(self.user_categories.get(user).unwrap_or(0));
+            let mut current = self.next;
+            while current != this {
+                let asset: contract_ref!(LAsset) = current.into();
+                let result = asset.health(user);
+
+                health.total_initial_collateral_value = health.total_initial_collateral_value.saturating_add(result.initial_collateral_value);
+                health.total_initial_debt_value = health.total_initial_debt_value.saturating_add(result.initial_debt_value);
+                health.total_maintenance_collateral_value = health.total_maintenance_collateral_value.saturating_add(result.maintenance_collateral_value);
+                health.total_maintenance_debt_value = health.total_maintenance_debt_value.saturating_add(result.maintenance_debt_value);
+                category_health.total_initial_collateral_value = category_health.total_initial_collateral_value.saturating_add(result.category_initial_collateral_value);
+                category_health.total_initial_debt_value = category_health.total_initial_debt_value.saturating_add(result.category_initial_debt_value);
+                category_health.total_maintenance_collateral_value = category_health.total_maintenance_collateral_value.saturating_add(result.category_maintenance_collateral_value);
+                category_health.total_maintenance_debt_value = category_health.total_maintenance_debt_value.saturating_add(result.category_maintenance_debt_value);
+                categories.position(result.category);
+                health.pools.push(PoolHealth {
+                    pool: current,
+                    initial_collateral_value: result.initial_collateral_value,
+                    initial_debt_value: result.initial_debt_value,
+                    maintenance_collateral_value: result.maintenance_collateral_value,
+                    maintenance_debt_value: result.maintenance_debt_value,
+                });
+                current = result.next;
+            }
+            if categories.applies() {
+                health.total_initial_collateral_value = category_health.total_initial_collateral_value;
+                health.total_initial_debt_value = category_health.total_initial_debt_value;
+                health.total_maintenance_collateral_value = category_health.total_maintenance_collateral_value;
+                health.total_maintenance_debt_value = category_health.total_maintenance_debt_value;
+            }
+            health
+        }
+
         #[ink(message)]
         pub fn flash_loan(&mut self, target_address: AccountId, pool_address: AccountId, amount: u128, data: Vec<u8>) -> Result<(), FlashLoanError>{
             let mut pool: contract_ref!(AssetPool) = pool_address.into();
@@ -123,15 +829,101 @@ mod admin {
         }
     }
 
+    #[cfg(test)]
+    thread_local! {
+        /// Pools of the off-chain tests by address, Admin calls them in place of the cross-contract calls
+        pub static POOLS: core::cell::RefCell<std::collections::HashMap<AccountId, finance2::LAssetContract>> = Default::default();
+    }
+
+    /// Runs `call` on the test pool at `pool` with Admin as the caller, panics like a call to an account without a contract
+    #[cfg(test)]
+    fn call_pool<R>(pool: AccountId, call: impl FnOnce(&mut finance2::LAssetContract) -> R) -> R {
+        let caller = ink::env::caller::<Environment>();
+        let this = ink::env::account_id::<Environment>();
+        ink::env::test::set_caller::<Environment>(this);
+        ink::env::test::set_callee::<Environment>(pool);
+        let result = POOLS.with_borrow_mut(|pools| pools.get_mut(&pool).map(call));
+        ink::env::test::set_caller::<Environment>(caller);
+        ink::env::test::set_callee::<Environment>(this);
+        result.unwrap_or_else(|| panic!("{:?} is not a pool", pool))
+    }
+
+    #[cfg(not(test))]
+    fn next_pool(pool: AccountId, this: AccountId) -> AccountId {
+        let pool: contract_ref!(LAsset) = pool.into();
+        pool.collateral_of(this).0
+    }
+
+    #[cfg(test)]
+    fn next_pool(pool: AccountId, this: AccountId) -> AccountId {
+        call_pool(pool, |pool| pool.collateral_of(this).0)
+    }
+
+    #[cfg(not(test))]
+    fn price_pool(pool: AccountId, price: u128, debt_price: u128, category_price: u128, price_scaler: u128, updated_at: u64) -> Result<AccountId, LAssetError> {
+        let mut pool: contract_ref!(AssetPool) = pool.into();
+        pool.set_price(price, debt_price, category_price, price_scaler, updated_at)
+    }
+
+    #[cfg(test)]
+    fn price_pool(pool: AccountId, price: u128, debt_price: u128, category_price: u128, price_scaler: u128, updated_at: u64) -> Result<AccountId, LAssetError> {
+        call_pool(pool, |pool| pool.set_price(price, debt_price, category_price, price_scaler, updated_at))
+    }
+
+    #[cfg(not(test))]
+    fn pause_pool(asset: AccountId, paused: PauseFlags) -> Result<(), LAssetError> {
+        let mut pool: contract_ref!(AssetPool) = asset.into();
+        pool.set_paused(paused)
+    }
+
+    #[cfg(test)]
+    fn pause_pool(asset: AccountId, paused: PauseFlags) -> Result<(), LAssetError> {
+        call_pool(asset, |pool| pool.set_paused(paused))
+    }
+
+    /// Middle of `prices`, the mean of the two middle ones for an even count
+    fn median(prices: &mut [u128]) -> u128 {
+        prices.sort_unstable();
+        let middle = prices.len() / 2;
+        if prices.len() % 2 == 1 {
+            prices[middle]
+        } else {
+            let (a, b) = (prices[middle - 1], prices[middle]);
+            a / 2 + b / 2 + (a % 2 + b % 2) / 2
+        }
+    }
+
     impl LAsset for Admin {
         #[ink(message)]
-        fn update(&mut self, _user: AccountId) -> UpdateResult {
-            UpdateResult::new(self.next) // it is possible to block withdraw and borrow from here
+        fn update(&mut self, user: AccountId) -> UpdateResult {
+            UpdateResult { // it is possible to block withdraw and borrow from here
+                user_category: self.user_categories.get(user).unwrap_or(0),
+                ..UpdateResult::new(self.next)
+            }
         }
 
         #[ink(message)]
-        fn repay_or_update(&mut self, _user: AccountId, _cash_owner: AccountId) -> UpdateOrRepayResult {
-            UpdateOrRepayResult::new(self.next) // it is possible to block liquidate from here
+        fn repay_or_update(&mut self, user: AccountId, _cash_owner: AccountId) -> UpdateOrRepayResult {
+            UpdateOrRepayResult { // it is possible to block liquidate from here
+                user_category: self.user_categories.get(user).unwrap_or(0),
+                ..UpdateOrRepayResult::new(self.next)
+            }
+        }
+
+        #[ink(message)]
+        fn collateral_of(&self, _user: AccountId) -> (AccountId, u128) {
+            (self.next, 0)
+        }
+
+        #[ink(message)]
+        fn health(&self, user: AccountId) -> UpdateOrRepayResult {
+            UpdateOrRepayResult {
+                user_category: self.user_categories.get(user).unwrap_or(0),
+                ..UpdateOrRepayResult::new(self.next)
+            }
         }
     }
 }
+
+#[cfg(test)]
+mod tests;
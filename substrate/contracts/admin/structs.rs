@@ -0,0 +1,77 @@
+use ink::primitives::AccountId;
+
+#[derive(Debug, Clone, PartialEq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct Price {
+    pub price: u128,
+    pub price_scaler: u128,
+    pub updated_at: u64,
+}
+
+/// How submissions of an asset's feeders are turned into a price
+#[derive(Debug, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct Aggregation {
+    pub price_scaler: u128,
+    pub min_submissions: u32,
+    pub max_age: u64,
+}
+
+/// Deviation of a feeder's submissions from the median of the other fresh ones, in the same fixed point as haircuts
+#[derive(Debug, Default, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct FeederStats {
+    pub submissions: u64,
+    pub compared: u64,
+    pub last_deviation: u128,
+    pub max_deviation: u128,
+    pub total_deviation: u128,
+}
+
+/// Time weighted average price accumulator, prices are rescaled to the `price_scaler` of the first observation.
+/// `cumulative` wraps around, so only differences over less than `u128::MAX / price` milliseconds are meaningful.
+#[derive(Debug, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct Twap {
+    pub price_scaler: u128,
+    pub price: u128,
+    pub cumulative: u128,
+    pub updated_at: u64,
+    /// Start of the averaged period, at least one window old once history allows
+    pub checkpoint_cumulative: u128,
+    pub checkpoint_at: u64,
+    /// Becomes the checkpoint once it is a window old
+    pub pending_cumulative: u128,
+    pub pending_at: u64,
+}
+
+/// Prices pushed outside these bounds are quarantined instead of applied.
+/// Prices are compared after rescaling to `price_scaler`, changes in the same fixed point as haircuts.
+#[derive(Debug, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct PriceBounds {
+    pub price_scaler: u128,
+    pub min_price: u128,
+    pub max_price: u128,
+    /// Largest move from the last accepted price, if it was accepted less than `window` before
+    pub max_change: u128,
+    pub window: u64,
+    /// Pauses borrows and liquidations of the asset when a price gets quarantined
+    pub auto_pause: bool,
+}
+
+/// Price attested off-chain by publishers, anyone may relay it together with their signatures
+#[derive(Debug, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct PricePayload {
+    pub asset: AccountId,
+    pub price: u128,
+    pub price_scaler: u128,
+    pub timestamp: u64,
+    pub nonce: u64,
+}
@@ -0,0 +1,536 @@
+use ink::env::hash::{HashOutput, Keccak256};
+use ink::prelude::vec;
+use ink::prelude::vec::Vec;
+use ink::primitives::{AccountId, Hash};
+use ink::scale::Encode;
+use ink::storage::traits::StorageKey;
+use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
+
+use crate::admin::{Admin, POOLS, STORAGE_VERSION};
+use crate::errors::AdminError;
+use crate::structs::{Aggregation, Price, PriceBounds, PricePayload};
+use finance2::LAssetContract;
+use finance2::errors::LAssetError;
+use finance2::logic::mulw;
+use finance2::migration::Encoded;
+use finance2::structs::{AssetParams, Category, PriceMode};
+
+fn setup_call(caller: AccountId, callee: AccountId, timestamp: u64) {
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(caller);
+    ink::env::test::set_callee::<ink::env::DefaultEnvironment>(callee);
+    ink::env::test::set_block_timestamp::<ink::env::DefaultEnvironment>(timestamp);
+}
+
+/// Puts a native pool in front of the chain, like `add_native_asset`
+fn add_pool(admin: &mut Admin, pool: AccountId) {
+    let caller = ink::env::caller::<ink::env::DefaultEnvironment>();
+    let this = ink::env::account_id::<ink::env::DefaultEnvironment>();
+    let timestamp = ink::env::block_timestamp::<ink::env::DefaultEnvironment>();
+    setup_call(this, pool, timestamp);
+    let contract = LAssetContract::new_native(admin.next, 1, 12);
+    POOLS.with_borrow_mut(|pools| pools.insert(pool, contract));
+    setup_call(caller, this, timestamp);
+    admin.next = pool;
+}
+
+/// Calls the test pool at `pool` as `caller`
+fn with_pool<R>(pool: AccountId, caller: AccountId, call: impl FnOnce(&mut LAssetContract) -> R) -> R {
+    let previous = ink::env::caller::<ink::env::DefaultEnvironment>();
+    let this = ink::env::account_id::<ink::env::DefaultEnvironment>();
+    let timestamp = ink::env::block_timestamp::<ink::env::DefaultEnvironment>();
+    setup_call(caller, pool, timestamp);
+    let result = POOLS.with_borrow_mut(|pools| call(pools.get_mut(&pool).unwrap()));
+    setup_call(previous, this, timestamp);
+    result
+}
+
+fn e<T: std::fmt::Debug>(m: &str, r: T) -> Result<(), String> {
+    Err(format!("{}. Got: {:?}", m, r))
+}
+
+fn sign(admin: AccountId, payload: &PricePayload, key: &SecretKey) -> [u8; 65] {
+    let mut hash = <Keccak256 as HashOutput>::Type::default();
+    ink::env::hash_encoded::<Keccak256, _>(&(admin, payload), &mut hash);
+    let (recovery_id, compact) = SECP256K1.sign_ecdsa_recoverable(&Message::from_digest(hash), key).serialize_compact();
+    let mut signature = [0; 65];
+    signature[..64].copy_from_slice(&compact);
+    signature[64] = recovery_id.to_i32() as u8;
+    signature
+}
+
+#[ink::test]
+fn push_signed_price_works() {
+    let dao = AccountId::from([0x1; 32]);
+    let this = AccountId::from([0x2; 32]);
+    let asset = AccountId::from([0x3; 32]);
+    let relayer = AccountId::from([0x8; 32]);
+    let keys = [[0x11; 32], [0x12; 32], [0x13; 32]].map(|k| SecretKey::from_slice(&k).unwrap());
+    let publishers = keys.map(|k| PublicKey::from_secret_key(SECP256K1, &k).serialize());
+
+    setup_call(dao, this, 10);
+    let mut admin = Admin::new(Hash::default());
+    let payload = PricePayload {asset, price: 5, price_scaler: 1, timestamp: 10, nonce: 0};
+    let signatures = keys.map(|k| sign(this, &payload, &k));
+    {
+        setup_call(relayer, this, 10);
+        match admin.push_signed_price(payload.clone(), signatures.to_vec()) {
+            Err(AdminError::PushSignedPriceDisabled) => Ok(()),
+            r => e("Push signed price should fail without quorum", r),
+        }.unwrap();
+        match admin.set_quorum(2) {
+            Err(AdminError::SetQuorumUnauthorized) => Ok(()),
+            r => e("Set quorum should fail if unauthorized", r),
+        }.unwrap();
+        match admin.add_publisher(publishers[0]) {
+            Err(AdminError::AddPublisherUnauthorized) => Ok(()),
+            r => e("Add publisher should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(dao, this, 10);
+        admin.set_quorum(2).unwrap();
+        admin.add_publisher(publishers[0]).unwrap();
+        admin.add_publisher(publishers[1]).unwrap();
+    }
+    {
+        setup_call(relayer, this, 10);
+        match admin.push_signed_price(payload.clone(), vec![signatures[0]]) {
+            Err(AdminError::PushSignedPriceQuorumNotReached) => Ok(()),
+            r => e("Push signed price should fail below quorum", r),
+        }.unwrap();
+        match admin.push_signed_price(payload.clone(), vec![signatures[0], signatures[0]]) {
+            Err(AdminError::PushSignedPriceDuplicateSigner) => Ok(()),
+            r => e("Push signed price should not count a signer twice", r),
+        }.unwrap();
+        match admin.push_signed_price(payload.clone(), vec![signatures[0], signatures[2]]) {
+            Err(AdminError::PushSignedPriceUnknownPublisher) => Ok(()),
+            r => e("Push signed price should fail for an unknown publisher", r),
+        }.unwrap();
+        let mut invalid = signatures[1];
+        invalid[..32].fill(0);
+        match admin.push_signed_price(payload.clone(), vec![signatures[0], invalid]) {
+            Err(AdminError::PushSignedPriceInvalidSignature) => Ok(()),
+            r => e("Push signed price should fail for an invalid signature", r),
+        }.unwrap();
+        let tampered = PricePayload {price: 6, ..payload.clone()};
+        match admin.push_signed_price(tampered, vec![signatures[0], signatures[1]]) {
+            Err(AdminError::PushSignedPriceUnknownPublisher) => Ok(()),
+            r => e("Push signed price should fail if the payload was changed", r),
+        }.unwrap();
+    }
+    {
+        setup_call(relayer, this, 9);
+        match admin.push_signed_price(payload.clone(), vec![signatures[0], signatures[1]]) {
+            Err(AdminError::PushSignedPriceFromFuture) => Ok(()),
+            r => e("Push signed price should fail if signed for a later time", r),
+        }.unwrap();
+
+        setup_call(relayer, this, 10);
+        admin.push_signed_price(payload.clone(), vec![signatures[1], signatures[0]]).unwrap();
+        let price = admin.prices.get(asset).unwrap();
+        assert_eq!((price.price, price.price_scaler, price.updated_at), (5, 1, 10));
+
+        match admin.push_signed_price(payload.clone(), vec![signatures[0], signatures[1]]) {
+            Err(AdminError::PushSignedPriceReplayed) => Ok(()),
+            r => e("Push signed price should fail if replayed", r),
+        }.unwrap();
+
+        let older = PricePayload {timestamp: 9, nonce: 1, ..payload.clone()};
+        let signatures = [sign(this, &older, &keys[0]), sign(this, &older, &keys[1])];
+        match admin.push_signed_price(older, signatures.to_vec()) {
+            Err(AdminError::PushSignedPriceOutdated) => Ok(()),
+            r => e("Push signed price should fail if older than the current price", r),
+        }.unwrap();
+    }
+    {
+        setup_call(dao, this, 10);
+        admin.remove_publisher(publishers[1]).unwrap();
+
+        setup_call(relayer, this, 10);
+        let newer = PricePayload {nonce: 2, ..payload.clone()};
+        let signatures = [sign(this, &newer, &keys[0]), sign(this, &newer, &keys[1])];
+        match admin.push_signed_price(newer, signatures.to_vec()) {
+            Err(AdminError::PushSignedPriceUnknownPublisher) => Ok(()),
+            r => e("Push signed price should fail for a removed publisher", r),
+        }.unwrap();
+    }
+}
+
+#[ink::test]
+fn migrate_works() {
+    let dao = AccountId::from([0x1; 32]);
+    let this = AccountId::from([0x2; 32]);
+    let guardian = AccountId::from([0x5; 32]);
+    let root_key = Admin::KEY;
+
+    // storage written by version 1 for an empty chain, mappings and lazy fields are kept under their own keys
+    setup_call(dao, this, 0);
+    let root = (1u32, dao, guardian, Hash::default(), this);
+    ink::env::set_contract_storage(&root_key, &Encoded(root.encode()));
+    let mut admin: Admin = ink::env::get_contract_storage(&root_key).unwrap().unwrap();
+    // entries of an asset outside the chain, like a category price source, keep the version 1 layout
+    let source = AccountId::from([0x6; 32]);
+    let mut params = AssetParams {collateral_cap: 5, ..Default::default()}.encode();
+    params.truncate(params.len() - (false, 0u128, 0u128, PriceMode::Spot, 0u128).encode().len());
+    ink::env::set_contract_storage(&(admin.params.key(), source), &Encoded(params));
+    ink::env::set_contract_storage(&(admin.prices.key(), source), &(7u128, 1u128));
+    {
+        setup_call(guardian, this, 0);
+        match admin.migrate() {
+            Err(AdminError::MigrateUnauthorized) => Ok(()),
+            r => e("Migrate should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(dao, this, 0);
+        admin.migrate().unwrap();
+        assert_eq!(admin.version, STORAGE_VERSION);
+        assert_eq!(admin.guardian, guardian);
+        assert_eq!(admin.quorum.get(), Some(0));
+
+        let params = admin.params(source);
+        assert_eq!((params.collateral_cap, params.max_price_age, params.twap_window), (5, 0, 0));
+        let price = admin.spot_price(source).unwrap();
+        assert_eq!((price.price, price.price_scaler, price.updated_at), (7, 1, 0));
+    }
+}
+
+#[ink::test]
+fn submit_price_works() {
+    let dao = AccountId::from([0x1; 32]);
+    let this = AccountId::from([0x2; 32]);
+    let asset = AccountId::from([0x3; 32]);
+    let feeders = [AccountId::from([0x11; 32]), AccountId::from([0x12; 32]), AccountId::from([0x13; 32])];
+    let outsider = AccountId::from([0x8; 32]);
+    let aggregation = Aggregation {price_scaler: 1, min_submissions: 2, max_age: 10};
+    let deviation = |price: u128, reference: u128| mulw(price.abs_diff(reference), u128::MAX).div(reference).unwrap();
+
+    setup_call(dao, this, 0);
+    let mut admin = Admin::new(Hash::default());
+    {
+        setup_call(outsider, this, 0);
+        match admin.set_feeders(asset, feeders.to_vec(), aggregation.clone()) {
+            Err(AdminError::SetFeedersUnauthorized) => Ok(()),
+            r => e("Set feeders should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(dao, this, 0);
+        match admin.set_feeders(asset, feeders.to_vec(), Aggregation {min_submissions: 0, ..aggregation.clone()}) {
+            Err(AdminError::SetFeedersInvalid) => Ok(()),
+            r => e("Set feeders should fail without required submissions", r),
+        }.unwrap();
+        match admin.set_feeders(asset, vec![feeders[0], feeders[1], feeders[0]], aggregation.clone()) {
+            Err(AdminError::SetFeedersInvalid) => Ok(()),
+            r => e("Set feeders should fail if a feeder is listed twice", r),
+        }.unwrap();
+        admin.set_feeders(asset, feeders.to_vec(), aggregation.clone()).unwrap();
+
+        setup_call(outsider, this, 0);
+        match admin.submit_price(asset, 100) {
+            Err(AdminError::SubmitPriceUnauthorized) => Ok(()),
+            r => e("Submit price should fail if not a feeder", r),
+        }.unwrap();
+    }
+    {
+        setup_call(feeders[0], this, 0);
+        match admin.submit_price(asset, 0) {
+            Err(AdminError::SubmitPriceZero) => Ok(()),
+            r => e("Submit price should fail for a zero price", r),
+        }.unwrap();
+        admin.submit_price(asset, 100).unwrap();
+        assert!(admin.median_price(asset).is_none());
+        let stats = admin.feeder_stats(asset, feeders[0]);
+        assert_eq!((stats.submissions, stats.compared), (1, 0));
+
+        setup_call(feeders[1], this, 0);
+        admin.submit_price(asset, 110).unwrap();
+        let price = admin.median_price(asset).unwrap();
+        assert_eq!((price.price, price.updated_at), (105, 0));
+        let stats = admin.feeder_stats(asset, feeders[1]);
+        assert_eq!((stats.submissions, stats.compared), (1, 1));
+        assert_eq!(stats.last_deviation, deviation(110, 100));
+
+        setup_call(feeders[2], this, 5);
+        admin.submit_price(asset, 120).unwrap();
+        let price = admin.median_price(asset).unwrap();
+        assert_eq!((price.price, price.updated_at), (110, 0));
+        assert_eq!(admin.feeder_stats(asset, feeders[2]).last_deviation, deviation(120, 105));
+    }
+    {
+        // the first two submissions are too old to count
+        setup_call(feeders[0], this, 11);
+        assert!(admin.median_price(asset).is_none());
+        admin.submit_price(asset, 90).unwrap();
+        let price = admin.median_price(asset).unwrap();
+        assert_eq!((price.price, price.updated_at), (105, 5));
+        let stats = admin.feeder_stats(asset, feeders[0]);
+        assert_eq!((stats.submissions, stats.compared), (2, 1));
+        assert_eq!(stats.last_deviation, deviation(90, 120));
+
+        setup_call(feeders[1], this, 12);
+        admin.submit_price(asset, 120).unwrap();
+        assert_eq!(admin.median_price(asset).unwrap().price, 120);
+        let stats = admin.feeder_stats(asset, feeders[1]);
+        assert_eq!((stats.submissions, stats.compared), (2, 2));
+        assert_eq!(stats.last_deviation, deviation(120, 105));
+        assert_eq!(stats.max_deviation, deviation(120, 105));
+        assert_eq!(stats.total_deviation, deviation(110, 100) + deviation(120, 105));
+    }
+    {
+        setup_call(dao, this, 12);
+        admin.set_feeders(asset, Vec::new(), aggregation.clone()).unwrap();
+        assert!(admin.median_price(asset).is_none());
+
+        setup_call(feeders[0], this, 12);
+        match admin.submit_price(asset, 100) {
+            Err(AdminError::SubmitPriceUnauthorized) => Ok(()),
+            r => e("Submit price should fail once the feeders are removed", r),
+        }.unwrap();
+    }
+}
+
+#[ink::test]
+fn spot_price_works() {
+    let dao = AccountId::from([0x1; 32]);
+    let this = AccountId::from([0x2; 32]);
+    let asset = AccountId::from([0x3; 32]);
+    let feeder = AccountId::from([0x11; 32]);
+
+    setup_call(dao, this, 0);
+    let mut admin = Admin::new(Hash::default());
+    admin.set_feeders(asset, vec![feeder], Aggregation {price_scaler: 1, min_submissions: 1, max_age: 100}).unwrap();
+    {
+        setup_call(feeder, this, 5);
+        admin.submit_price(asset, 50).unwrap();
+        setup_call(dao, this, 10);
+        admin.push_price(asset, 100, 1).unwrap();
+
+        // the median dates from the submission, older than the pushed price
+        let price = admin.spot_price(asset).unwrap();
+        assert_eq!((price.price, price.updated_at), (100, 10));
+    }
+    {
+        setup_call(feeder, this, 11);
+        admin.submit_price(asset, 60).unwrap();
+        let price = admin.spot_price(asset).unwrap();
+        assert_eq!((price.price, price.updated_at), (60, 11));
+    }
+}
+
+#[ink::test]
+fn price_bounds_work() {
+    let dao = AccountId::from([0x1; 32]);
+    let this = AccountId::from([0x2; 32]);
+    let asset = AccountId::from([0x3; 32]);
+    let guardian = AccountId::from([0x5; 32]);
+    let feeder = AccountId::from([0x11; 32]);
+    let outsider = AccountId::from([0x8; 32]);
+    let bounds = PriceBounds {price_scaler: 10, min_price: 500, max_price: 2000, max_change: u128::MAX / 10, window: 10, auto_pause: false};
+
+    setup_call(dao, this, 0);
+    let mut admin = Admin::new(Hash::default());
+    admin.set_guardian(guardian).unwrap();
+    add_pool(&mut admin, asset);
+    admin.push_price(asset, 100, 1).unwrap();
+    {
+        setup_call(outsider, this, 0);
+        match admin.set_price_bounds(asset, Some(bounds.clone())) {
+            Err(AdminError::SetPriceBoundsUnauthorized) => Ok(()),
+            r => e("Set price bounds should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(dao, this, 0);
+        admin.set_price_bounds(asset, Some(bounds.clone())).unwrap();
+        admin.push_price(asset, 40, 1).unwrap();
+        assert_eq!(admin.prices.get(asset).unwrap().price, 100);
+        assert_eq!(admin.quarantined_price(asset).unwrap().price, 40);
+
+        setup_call(dao, this, 1);
+        admin.push_price(asset, 105, 1).unwrap();
+        assert_eq!(admin.prices.get(asset).unwrap().price, 105);
+
+        // more than 10% from the last price within the window
+        setup_call(dao, this, 2);
+        admin.push_price(asset, 120, 1).unwrap();
+        assert_eq!(admin.prices.get(asset).unwrap().price, 105);
+        assert_eq!(admin.quarantined_price(asset).unwrap().price, 120);
+    }
+    {
+        setup_call(outsider, this, 2);
+        match admin.confirm_price(asset) {
+            Err(AdminError::ConfirmPriceUnauthorized) => Ok(()),
+            r => e("Confirm price should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(dao, this, 2);
+        match admin.confirm_price(asset) {
+            Err(AdminError::ConfirmPriceSameAuthority) => Ok(()),
+            r => e("Confirm price should fail for the pusher", r),
+        }.unwrap();
+
+        setup_call(guardian, this, 2);
+        admin.confirm_price(asset).unwrap();
+        let price = admin.prices.get(asset).unwrap();
+        assert_eq!((price.price, price.updated_at), (120, 2));
+        assert!(admin.quarantined_price(asset).is_none());
+        match admin.confirm_price(asset) {
+            Err(AdminError::ConfirmPriceNotQuarantined) => Ok(()),
+            r => e("Confirm price should fail without a quarantined price", r),
+        }.unwrap();
+
+        setup_call(dao, this, 3);
+        admin.push_price(asset, 300, 1).unwrap();
+        setup_call(dao, this, 4);
+        admin.push_price(asset, 121, 1).unwrap();
+        setup_call(guardian, this, 4);
+        match admin.confirm_price(asset) {
+            Err(AdminError::ConfirmPriceOutdated) => Ok(()),
+            r => e("Confirm price should fail if older than the current price", r),
+        }.unwrap();
+    }
+    {
+        setup_call(dao, this, 5);
+        admin.set_price_bounds(asset, Some(PriceBounds {auto_pause: true, ..bounds.clone()})).unwrap();
+        admin.push_price(asset, 40, 1).unwrap();
+        assert_eq!(admin.prices.get(asset).unwrap().price, 121);
+        let paused = admin.paused.get(asset).unwrap();
+        assert!(paused.borrow && paused.liquidate && !paused.deposit);
+        with_pool(asset, outsider, |pool| {
+            assert_eq!(pool.paused, paused);
+            match pool.borrow(1) {
+                Err(LAssetError::BorrowPaused) => Ok(()),
+                r => e("Borrow should fail once the pool is paused", r),
+            }.unwrap();
+            match pool.liquidate(dao) {
+                Err(LAssetError::LiquidatePaused) => Ok(()),
+                r => e("Liquidate should fail once the pool is paused", r),
+            }.unwrap();
+        });
+    }
+    {
+        // accounts outside the chain are only quarantined, a call to pause them would trap
+        let source = AccountId::from([0x6; 32]);
+        admin.set_price_bounds(source, Some(PriceBounds {auto_pause: true, ..bounds.clone()})).unwrap();
+        admin.push_price(source, 40, 1).unwrap();
+        assert_eq!(admin.quarantined_price(source).unwrap().price, 40);
+        assert!(admin.paused.get(source).is_none());
+
+        admin.set_feeders(source, vec![feeder], Aggregation {price_scaler: 1, min_submissions: 1, max_age: 10}).unwrap();
+        setup_call(feeder, this, 5);
+        admin.submit_price(source, 5000).unwrap();
+        assert!(admin.spot_price(source).is_none());
+        assert_eq!(admin.quarantined_price(source).unwrap().price, 5000);
+    }
+    {
+        // aggregated prices pass the same bounds
+        setup_call(dao, this, 6);
+        admin.set_feeders(asset, vec![feeder], Aggregation {price_scaler: 1, min_submissions: 1, max_age: 10}).unwrap();
+        setup_call(feeder, this, 6);
+        admin.submit_price(asset, 125).unwrap();
+        let price = admin.spot_price(asset).unwrap();
+        assert_eq!((price.price, price.updated_at), (125, 6));
+
+        setup_call(feeder, this, 7);
+        admin.submit_price(asset, 5000).unwrap();
+        assert_eq!(admin.spot_price(asset).unwrap().price, 125);
+        assert_eq!(admin.quarantined_price(asset).unwrap().price, 5000);
+
+        // Admin pushed it, so either authority may confirm
+        setup_call(dao, this, 7);
+        admin.confirm_price(asset).unwrap();
+        assert_eq!(admin.prices.get(asset).unwrap().price, 5000);
+    }
+}
+
+#[ink::test]
+fn twap_works() {
+    let dao = AccountId::from([0x1; 32]);
+    let this = AccountId::from([0x2; 32]);
+    let asset = AccountId::from([0x3; 32]);
+    let params = AssetParams {price_mode: PriceMode::Twap, twap_window: 100, ..Default::default()};
+
+    setup_call(dao, this, 0);
+    let mut admin = Admin::new(Hash::default());
+    admin.push_params(asset, params.clone()).unwrap();
+    admin.push_price(asset, 100, 1).unwrap();
+    {
+        // no time has passed since the first observation
+        assert!(admin.twap(asset).is_none());
+        assert_eq!(admin.mode_prices(asset, &Price {price: 100, price_scaler: 1, updated_at: 0}), (100, 100));
+
+        setup_call(dao, this, 50);
+        admin.push_price(asset, 200, 1).unwrap();
+
+        setup_call(dao, this, 100);
+        let twap = admin.twap(asset).unwrap();
+        assert_eq!((twap.price, twap.price_scaler, twap.updated_at), (150, 1, 50));
+    }
+    {
+        let spot = Price {price: 200, price_scaler: 1, updated_at: 50};
+        assert_eq!(admin.mode_prices(asset, &spot), (150, 150));
+
+        admin.push_params(asset, AssetParams {price_mode: PriceMode::Conservative, ..params.clone()}).unwrap();
+        assert_eq!(admin.mode_prices(asset, &spot), (150, 200));
+        // the TWAP is rescaled to the spot's price_scaler
+        assert_eq!(admin.mode_prices(asset, &Price {price: 2000, price_scaler: 10, updated_at: 50}), (1500, 2000));
+
+        admin.push_params(asset, AssetParams {price_mode: PriceMode::Spot, ..params.clone()}).unwrap();
+        assert_eq!(admin.mode_prices(asset, &spot), (200, 200));
+    }
+    {
+        // the checkpoint moves once the pending one is a window old
+        admin.push_price(asset, 100, 1).unwrap();
+        setup_call(dao, this, 150);
+        assert_eq!(admin.twap(asset).unwrap().price, 133);
+
+        setup_call(dao, this, 200);
+        admin.push_price(asset, 1000, 10).unwrap();
+        let twap = admin.twap(asset).unwrap();
+        assert_eq!((twap.price, twap.price_scaler, twap.updated_at), (100, 1, 200));
+        assert_eq!(admin.twaps.get(asset).unwrap().checkpoint_at, 100);
+    }
+}
+
+#[ink::test]
+fn pull_prices_works() {
+    let dao = AccountId::from([0x1; 32]);
+    let this = AccountId::from([0x2; 32]);
+    let a = AccountId::from([0x3; 32]);
+    let b = AccountId::from([0x4; 32]);
+    let source = AccountId::from([0x5; 32]);
+    let feeder = AccountId::from([0x11; 32]);
+
+    setup_call(dao, this, 0);
+    let mut admin = Admin::new(Hash::default());
+    add_pool(&mut admin, a);
+    add_pool(&mut admin, b);
+    admin.push_price(a, 10, 1).unwrap();
+    admin.push_price(b, 20, 1).unwrap();
+    admin.push_category(1, Category {price_source: Some(source), ..Default::default()}).unwrap();
+    admin.set_asset_category(a, 1).unwrap();
+    admin.set_asset_category(b, 1).unwrap();
+    admin.set_feeders(source, vec![feeder], Aggregation {price_scaler: 1, min_submissions: 1, max_age: 100}).unwrap();
+    setup_call(feeder, this, 0);
+    admin.submit_price(source, 20).unwrap();
+    admin.pull_prices();
+    {
+        setup_call(feeder, this, 10);
+        admin.submit_price(source, 30).unwrap();
+        admin.pull_prices();
+
+        for (pool, price) in [(a, 10), (b, 20)] {
+            with_pool(pool, dao, |pool| {
+                assert_eq!((pool.price, pool.category_price.get(), pool.price_updated_at.get()), (price, Some(30), Some(0)));
+            });
+        }
+        // both members share the source, yet its median got observed once.
+        // A second observation at the same time would have moved the checkpoint to now, as twap_window is zero
+        assert_eq!(admin.twaps.get(source).unwrap().checkpoint_at, 0);
+        assert_eq!(admin.twap(source).unwrap().price, 20);
+    }
+    {
+        // unchanged prices are not observed again
+        setup_call(feeder, this, 20);
+        admin.pull_prices();
+        assert_eq!(admin.twaps.get(source).unwrap().checkpoint_at, 0);
+        assert_eq!(admin.twaps.get(a).unwrap().checkpoint_at, 0);
+    }
+}
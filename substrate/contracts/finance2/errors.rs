@@ -1,3 +1,4 @@
+use ink::primitives::AccountId;
 use traits::psp22::PSP22Error;
 
 #[derive(Debug)]
@@ -6,27 +7,49 @@ pub enum LAssetError {
     DepositOverflow,
     DepositTransferFailed(PSP22Error),
     FirstDepositRequiresGasCollateral,
-    DepositWhileBorrowingNotAllowed,
+    DepositCapExceeded,
+    DepositPaused,
+    DepositReduceOnly,
+    DepositForNotApproved,
 
     WithdrawOverflow,
     WithdrawTransferFailed(PSP22Error),
     WithdrawWithoutDeposit,
     CollateralValueTooLowAfterWithdraw,
+    WithdrawPaused,
+    WithdrawRemoved,
+    WithdrawIsolatedWithOtherCollateral,
+    WithdrawNotAllowedInIsolation,
+    WithdrawStalePrice(AccountId),
 
     MintOverflow,
     MintTransferFailed(PSP22Error),
     MintFeeOverflow,
+    MintCapExceeded,
+    MintPaused,
+    MintReduceOnly,
+    MintForNothing,
 
     BurnOverflow,
     BurnTooMuch,
     BurnTransferFailed(PSP22Error),
+    BurnPaused,
     
     BorrowOverflow,
     BorrowFeeOverflow,
     FirstBorrowRequiresGasCollateral,
+    BorrowUnexpectedValue,
     BorrowTransferFailed(PSP22Error),
     CollateralValueTooLowAfterBorrow,
-    BorrowWhileDepositingNotAllowed,
+    BorrowCapExceeded,
+    BorrowOnBehalfNotDelegated,
+    BorrowPaused,
+    BorrowRemoved,
+    BorrowReduceOnly,
+    BorrowStalePrice(AccountId),
+    BorrowIsolatedWithOtherCollateral,
+    BorrowNotAllowedInIsolation,
+    BorrowIsolatedCeilingExceeded,
 
     DepositCashTransferFailed(PSP22Error),
     DepositCashOverflow,
@@ -37,14 +60,61 @@ pub enum LAssetError {
     LiquidateTooMuch,
     LiquidateTooEarly,
     LiquidateTransferFailed(PSP22Error),
+    LiquidateNotFlagged,
+    LiquidatePaused,
+    LiquidateRemoved,
+    LiquidateStalePrice(AccountId),
+
+    FlagForNothing,
+    FlagStalePrice(AccountId),
+    FlagRemoved,
+
+    WriteOffWithoutBorrow,
+    WriteOffWithCollateral,
+    WriteOffRemoved,
 
     RepayWithoutBorrow,
     RepayTransferFailed(PSP22Error),
     RepayCashOverflow,
+    RepayPaused,
+
+    SyncUnathorized,
+    SyncNativeNotSupported,
+    SyncNotBalanceDelta,
+    SyncOverflow,
+    SyncDeficitTooLarge,
 
     SetPriceUnathorized,
 
     SetParamsUnathorized,
+
+    SetPausedUnathorized,
+
+    SetCategoryUnathorized,
+
+    SetStatusUnathorized,
+    SetStatusRemoved,
+
+    RemoveUnathorized,
+    RemoveNotReduceOnly,
+    RemoveNotEmpty,
+
+    RelinkUnathorized,
+
+    UpgradeUnathorized,
+    UpgradeFailed,
+
+    MigrateUnathorized,
+    MigrateFromNewerVersion,
+
+    WithdrawReserveUnathorized,
+    WithdrawReserveOverflow,
+    WithdrawReserveTooMuch,
+    WithdrawReserveTransferFailed(PSP22Error),
+
+    SkimUnathorized,
+    SkimNativeNotSupported,
+    SkimTransferFailed(PSP22Error),
 }
 
 #[derive(Debug)]
@@ -53,4 +123,6 @@ pub enum TakeCashError {
     Unauthorized,
     Overflow,
     Transfer(PSP22Error),
+    NativeNotSupported,
+    Paused,
 }
\ No newline at end of file
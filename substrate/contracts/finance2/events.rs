@@ -0,0 +1,228 @@
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+use crate::structs::{AssetParams, AssetStatus, Category, PauseFlags};
+
+#[ink::event]
+pub struct Deposit {
+    #[ink(topic)]
+    pub user: AccountId,
+    pub payer: AccountId,
+    pub amount: u128,
+    pub total_collateral: u128,
+}
+
+#[ink::event]
+pub struct Withdraw {
+    #[ink(topic)]
+    pub user: AccountId,
+    pub recipient: AccountId,
+    pub amount: u128,
+    pub total_collateral: u128,
+}
+
+#[ink::event]
+pub struct Mint {
+    #[ink(topic)]
+    pub user: AccountId,
+    pub payer: AccountId,
+    pub amount: u128,
+    pub fee: u128,
+    pub shares: u128,
+    pub total_shares: u128,
+    pub total_liquidity: u128,
+    pub total_borrowable: u128,
+    pub total_reserve: u128,
+}
+
+#[ink::event]
+pub struct Burn {
+    #[ink(topic)]
+    pub user: AccountId,
+    pub recipient: AccountId,
+    pub amount: u128,
+    pub shares: u128,
+    pub total_shares: u128,
+    pub total_liquidity: u128,
+    pub total_borrowable: u128,
+}
+
+#[ink::event]
+pub struct Borrow {
+    #[ink(topic)]
+    pub user: AccountId,
+    pub recipient: AccountId,
+    pub amount: u128,
+    pub fee: u128,
+    pub bonds: u128,
+    pub total_bonds: u128,
+    pub total_liquidity: u128,
+    pub total_borrowable: u128,
+    pub total_reserve: u128,
+}
+
+#[ink::event]
+pub struct DepositorApproval {
+    #[ink(topic)]
+    pub user: AccountId,
+    #[ink(topic)]
+    pub depositor: AccountId,
+    pub approved: bool,
+}
+
+#[ink::event]
+pub struct Delegation {
+    #[ink(topic)]
+    pub owner: AccountId,
+    #[ink(topic)]
+    pub delegatee: AccountId,
+    pub amount: u128,
+}
+
+#[ink::event]
+pub struct Repay {
+    #[ink(topic)]
+    pub cash_owner: AccountId,
+    #[ink(topic)]
+    pub user: AccountId,
+    pub amount: u128,
+    pub bonds: u128,
+    pub total_bonds: u128,
+    pub total_liquidity: u128,
+    pub total_borrowable: u128,
+}
+
+#[ink::event]
+pub struct Liquidate {
+    #[ink(topic)]
+    pub liquidator: AccountId,
+    #[ink(topic)]
+    pub user: AccountId,
+    pub qouted_repaid: u128,
+    /// Pools whose debt got repaid, with the quoted amount of each. Their `Repay` events hold the burnt bonds
+    pub debt_pools: Vec<(AccountId, u128)>,
+    pub amount: u128,
+    pub total_collateral: u128,
+}
+
+#[ink::event]
+pub struct Flag {
+    #[ink(topic)]
+    pub user: AccountId,
+    pub flagged_at: Option<u64>,
+}
+
+#[ink::event]
+pub struct WriteOff {
+    #[ink(topic)]
+    pub caller: AccountId,
+    #[ink(topic)]
+    pub user: AccountId,
+    pub amount: u128,
+    pub bonds: u128,
+    pub from_reserve: u128,
+    pub total_bonds: u128,
+    pub total_liquidity: u128,
+    pub total_reserve: u128,
+}
+
+#[ink::event]
+pub struct DepositCash {
+    #[ink(topic)]
+    pub owner: AccountId,
+    #[ink(topic)]
+    pub spender: AccountId,
+    pub amount: u128,
+    pub cash: u128,
+}
+
+#[ink::event]
+pub struct WithdrawCash {
+    #[ink(topic)]
+    pub owner: AccountId,
+    pub amount: u128,
+}
+
+#[ink::event]
+pub struct TakeCash {
+    #[ink(topic)]
+    pub target: AccountId,
+    pub amount: u128,
+    pub fee: u128,
+    pub total_liquidity: u128,
+    pub total_borrowable: u128,
+    pub total_reserve: u128,
+}
+
+#[ink::event]
+pub struct WithdrawReserve {
+    #[ink(topic)]
+    pub target: AccountId,
+    pub amount: u128,
+    pub total_liquidity: u128,
+    pub total_borrowable: u128,
+    pub total_reserve: u128,
+}
+
+#[ink::event]
+pub struct Skim {
+    #[ink(topic)]
+    pub target: AccountId,
+    pub amount: u128,
+}
+
+#[ink::event]
+pub struct Sync {
+    pub total_liquidity: u128,
+    pub total_borrowable: u128,
+    pub total_reserve: u128,
+}
+
+#[ink::event]
+pub struct Accrue {
+    pub interest: u128,
+    pub total_liquidity: u128,
+    pub total_reserve: u128,
+    pub updated_at: u64,
+}
+
+#[ink::event]
+pub struct SetPrice {
+    pub price: u128,
+    pub debt_price: u128,
+    pub category_price: u128,
+    pub price_scaler: u128,
+    pub updated_at: u64,
+}
+
+#[ink::event]
+pub struct SetParams {
+    pub params: AssetParams,
+}
+
+#[ink::event]
+pub struct SetPaused {
+    pub paused: PauseFlags,
+}
+
+#[ink::event]
+pub struct SetCategory {
+    pub category: u32,
+    pub params: Category,
+}
+
+#[ink::event]
+pub struct SetStatus {
+    pub status: AssetStatus,
+}
+
+#[ink::event]
+pub struct Relink {
+    pub removed: AccountId,
+    pub next: AccountId,
+}
+
+#[ink::event]
+pub struct Migrate {
+    pub from: u32,
+    pub to: u32,
+}
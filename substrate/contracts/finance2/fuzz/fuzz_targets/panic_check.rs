@@ -10,6 +10,7 @@ enum Method {
         to_deposit: u128,
         value: bool,
         allowance: u128,
+        user: Option<u8>,
     },
     Withdraw {
         time_delta: u32,
@@ -17,6 +18,7 @@ enum Method {
         callee: Option<bool>,
         to_withdraw: u128,
         transfer_error: bool,
+        recipient: Option<u8>,
     },
     Mint {
         time_delta: u32,
@@ -24,6 +26,7 @@ enum Method {
         callee: Option<bool>,
         to_wrap: u128,
         allowance: u128,
+        recipient: Option<u8>,
     },
     Burn {
         time_delta: u32,
@@ -31,6 +34,7 @@ enum Method {
         callee: Option<bool>,
         to_burn: u128,
         transfer_error: bool,
+        recipient: Option<u8>,
     },
     Borrow {
         time_delta: u32,
@@ -40,6 +44,27 @@ enum Method {
         value: bool, 
         transfer_error: bool,
     },
+    BorrowOnBehalf {
+        time_delta: u32,
+        caller: u8,
+        callee: Option<bool>,
+        owner: u8,
+        to_borrow: u128,
+        value: bool, 
+        transfer_error: bool,
+    },
+    ApproveDelegation {
+        caller: u8,
+        callee: Option<bool>,
+        delegatee: u8,
+        amount: u128,
+    },
+    ApproveDepositor {
+        caller: u8,
+        callee: Option<bool>,
+        depositor: u8,
+        approved: bool,
+    },
     DepositCash {
         caller: u8,
         callee: Option<bool>,
@@ -59,11 +84,36 @@ enum Method {
         user: u8,
         transfer_error: bool,
     },
+    Flag {
+        time_delta: u32,
+        caller: u8,
+        callee: Option<bool>,
+        user: u8,
+    },
+    WriteOff {
+        time_delta: u32,
+        caller: u8,
+        callee: Option<bool>,
+        user: u8,
+    },
     Accrue {
         time_delta: u32,
         caller: u8,
         callee: Option<bool>,
     },
+    Sync {
+        time_delta: u32,
+        is_admin: bool,
+        callee: Option<bool>,
+        balance: u128,
+    },
+    Skim {
+        is_admin: bool,
+        callee: Option<bool>,
+        target: u8,
+        balance: u128,
+        transfer_error: bool,
+    },
     Repay {
         time_delta: u32,
         caller: u8,
@@ -92,10 +142,34 @@ enum Method {
         target: u8,
         transfer_error: bool,
     },
+    Migrate {
+        is_admin: bool,
+        callee: Option<bool>,
+    },
+    SetPaused {
+        is_admin: bool,
+        callee: Option<bool>,
+        flags: u8,
+    },
+    SetCategory {
+        is_admin: bool,
+        callee: Option<bool>,
+        category: u32,
+        initial_margin: u128,
+        maintenance_margin: u128,
+        initial_haircut: u128,
+        maintenance_haircut: u128,
+    },
+    SetStatus {
+        is_admin: bool,
+        callee: Option<bool>,
+        reduce_only: bool,
+    },
     SetPrice {
         is_admin: bool,
         callee: Option<bool>,
         price: u128,
+        category_price: u128,
         price_scaler: u128,
     },
     SetParams {
@@ -113,6 +187,19 @@ enum Method {
         borrow_fee: u128,
         take_cash_fee: u128,
         liquidation_reward: u128,
+        collateral_cap: u128,
+        liquidity_cap: u128,
+        debt_cap: u128,
+        reserve_factor: u128,
+        close_factor: u128,
+        max_liquidation_reward: u128,
+        auction_duration: u128,
+        balance_delta: bool,
+        max_price_age: u128,
+        twap: Option<bool>,
+        twap_window: u128,
+        isolated: bool,
+        isolated_debt_ceiling: u128,
     },
     Transfer {
         caller: u8,
@@ -170,7 +257,7 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
         (&mut TIMESTAMP, &mut TRANSFER_ERROR, BALANCES.as_mut().unwrap(), L_BTC.as_mut().unwrap(), L_USDC.as_mut().unwrap(), L_ETH.as_mut().unwrap())
     };
     match method {
-        Method::Deposit { caller, callee, to_deposit, value, allowance } => {
+        Method::Deposit { caller, callee, to_deposit, value, allowance, user } => {
             let caller = AccountId::from([caller; 32]);
             let contract = match callee {
                 Some(true) => l_btc,
@@ -186,10 +273,13 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             let key = (callee, caller);
             balances.insert(key, allowance);
             setup_call(caller, callee, value, *timestamp);
-            let _ = contract.deposit(to_deposit);
+            let _ = match user {
+                Some(user) => contract.deposit_for(AccountId::from([user; 32]), to_deposit),
+                None => contract.deposit(to_deposit),
+            };
             balances.remove(&key);
         },
-        Method::Withdraw { time_delta, caller, callee, to_withdraw, transfer_error } => {
+        Method::Withdraw { time_delta, caller, callee, to_withdraw, transfer_error, recipient } => {
             let caller = AccountId::from([caller; 32]);
             let contract = match callee {
                 Some(true) => l_btc,
@@ -204,10 +294,13 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             *timestamp += time_delta as u64;
             setup_call(caller, callee, 0, *timestamp);
             *t_error = transfer_error;
-            let _ = contract.withdraw(to_withdraw);
+            let _ = match recipient {
+                Some(recipient) => contract.withdraw_to(AccountId::from([recipient; 32]), to_withdraw),
+                None => contract.withdraw(to_withdraw),
+            };
             *t_error = false;
         },
-        Method::Mint { time_delta, caller, callee, to_wrap, allowance } => {
+        Method::Mint { time_delta, caller, callee, to_wrap, allowance, recipient } => {
             let caller = AccountId::from([caller; 32]);
             let contract = match callee {
                 Some(true) => l_btc,
@@ -223,10 +316,13 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             balances.insert(key, allowance);
             *timestamp += time_delta as u64;
             setup_call(caller, callee, 0, *timestamp);
-            let _ = contract.mint(to_wrap);
+            let _ = match recipient {
+                Some(recipient) => contract.mint_to(AccountId::from([recipient; 32]), to_wrap),
+                None => contract.mint(to_wrap),
+            };
             balances.remove(&key);
         },
-        Method::Burn { time_delta, caller, callee, to_burn, transfer_error } => {
+        Method::Burn { time_delta, caller, callee, to_burn, transfer_error, recipient } => {
             let caller = AccountId::from([caller; 32]);
             let contract = match callee {
                 Some(true) => l_btc,
@@ -241,7 +337,10 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             *timestamp += time_delta as u64;
             setup_call(caller, callee, 0, *timestamp);
             *t_error = transfer_error;
-            let _ = contract.burn(to_burn);
+            let _ = match recipient {
+                Some(recipient) => contract.burn_to(AccountId::from([recipient; 32]), to_burn),
+                None => contract.burn(to_burn),
+            };
             *t_error = false;
         },
         Method::Borrow { time_delta, caller, callee, to_borrow, value, transfer_error } => {
@@ -262,6 +361,57 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             let _ = contract.borrow(to_borrow);
             *t_error = false;
         },
+        Method::BorrowOnBehalf { time_delta, caller, callee, owner, to_borrow, value, transfer_error } => {
+            let caller = AccountId::from([caller; 32]);
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            let owner = AccountId::from([owner; 32]);
+            *timestamp += time_delta as u64;
+            setup_call(caller, callee, value as u128, *timestamp);
+            *t_error = transfer_error;
+            let _ = contract.borrow_on_behalf(owner, to_borrow);
+            *t_error = false;
+        },
+        Method::ApproveDelegation { caller, callee, delegatee, amount } => {
+            let caller = AccountId::from([caller; 32]);
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            let delegatee = AccountId::from([delegatee; 32]);
+            setup_call(caller, callee, 0, *timestamp);
+            let _ = contract.approve_delegation(delegatee, amount);
+        },
+        Method::ApproveDepositor { caller, callee, depositor, approved } => {
+            let caller = AccountId::from([caller; 32]);
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            let depositor = AccountId::from([depositor; 32]);
+            setup_call(caller, callee, 0, *timestamp);
+            let _ = contract.approve_depositor(depositor, approved);
+        },
         Method::DepositCash { caller, callee, extra_cash, spender, allowance } => {
             let caller = AccountId::from([caller; 32]);
             let contract = match callee {
@@ -322,6 +472,40 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             let _ = contract.liquidate(user);
             *t_error = false;
         },
+        Method::Flag { time_delta, caller, callee, user } => {
+            let caller = AccountId::from([caller; 32]);
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let user = AccountId::from([user; 32]);
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            *timestamp += time_delta as u64;
+            setup_call(caller, callee, 0, *timestamp);
+            let _ = contract.flag(user);
+        },
+        Method::WriteOff { time_delta, caller, callee, user } => {
+            let caller = AccountId::from([caller; 32]);
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let user = AccountId::from([user; 32]);
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            *timestamp += time_delta as u64;
+            setup_call(caller, callee, 0, *timestamp);
+            let _ = contract.write_off(user);
+        },
         Method::Accrue { time_delta, caller, callee } => {
             let caller = AccountId::from([caller; 32]);
             let contract = match callee {
@@ -393,6 +577,42 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             setup_call(caller, callee, 0, *timestamp);
             let _ = contract.update(user);
         },
+        Method::Sync { time_delta, is_admin, callee, balance } => {
+            let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            balances.insert((callee, callee), balance);
+            *timestamp += time_delta as u64;
+            setup_call(caller, callee, 0, *timestamp);
+            let _ = contract.sync();
+        },
+        Method::Skim { is_admin, callee, target, balance, transfer_error } => {
+            let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            let target = AccountId::from([target; 32]);
+            balances.insert((callee, callee), balance);
+            setup_call(caller, callee, 0, *timestamp);
+            *t_error = transfer_error;
+            let _ = contract.skim(target);
+            *t_error = false;
+        },
         Method::TakeCash { is_admin, callee, amount, target, transfer_error } => {
             let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
             let contract = match callee {
@@ -411,7 +631,85 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
             let _ = contract.take_cash(amount, target);
             *t_error = false;
         },
-        Method::SetPrice { is_admin, callee, price, price_scaler } => {
+        Method::Migrate { is_admin, callee } => {
+            let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            setup_call(caller, callee, 0, *timestamp);
+            let _ = contract.migrate();
+        },
+        Method::SetCategory { is_admin, callee, category, initial_margin, maintenance_margin, initial_haircut, maintenance_haircut } => {
+            let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            setup_call(caller, callee, 0, *timestamp);
+            let params = Category {
+                initial_margin,
+                maintenance_margin,
+                initial_haircut,
+                maintenance_haircut,
+                price_source: None,
+            };
+            let _ = contract.set_category(category, params);
+        },
+        Method::SetStatus { is_admin, callee, reduce_only } => {
+            let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            setup_call(caller, callee, 0, *timestamp);
+            let status = if reduce_only { AssetStatus::ReduceOnly } else { AssetStatus::Active };
+            let _ = contract.set_status(status);
+        },
+        Method::SetPaused { is_admin, callee, flags } => {
+            let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
+            let contract = match callee {
+                Some(true) => l_btc,
+                Some(false) => l_usdc,
+                None => l_eth,
+            };
+            let callee = match callee {
+                Some(true) => btc,
+                Some(false) => usdc,
+                None => eth,
+            };
+            setup_call(caller, callee, 0, *timestamp);
+            let paused = PauseFlags {
+                deposit: flags & 1 != 0,
+                withdraw: flags & 2 != 0,
+                mint: flags & 4 != 0,
+                burn: flags & 8 != 0,
+                borrow: flags & 16 != 0,
+                repay: flags & 32 != 0,
+                liquidate: flags & 64 != 0,
+                take_cash: flags & 128 != 0,
+            };
+            let _ = contract.set_paused(paused);
+        },
+        Method::SetPrice { is_admin, callee, price, category_price, price_scaler } => {
             let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
             let contract = match callee {
                 Some(true) => l_btc,
@@ -424,9 +722,9 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
                 None => eth,
             };
             setup_call(caller, callee, 0, *timestamp);
-            let _ = contract.set_price(price, price_scaler);
+            let _ = contract.set_price(price, price, category_price, price_scaler, *timestamp);
         },
-        Method::SetParams { is_admin, callee, standard_rate, standard_min_rate, emergency_rate, emergency_max_rate, initial_margin, maintenance_margin, initial_haircut, maintenance_haircut, mint_fee, borrow_fee, take_cash_fee, liquidation_reward } => {
+        Method::SetParams { is_admin, callee, standard_rate, standard_min_rate, emergency_rate, emergency_max_rate, initial_margin, maintenance_margin, initial_haircut, maintenance_haircut, mint_fee, borrow_fee, take_cash_fee, liquidation_reward, collateral_cap, liquidity_cap, debt_cap, reserve_factor, close_factor, max_liquidation_reward, auction_duration, balance_delta, max_price_age, twap, twap_window, isolated, isolated_debt_ceiling } => {
             let caller = if is_admin { admin } else { AccountId::from([0x1; 32]) };
             let contract = match callee {
                 Some(true) => l_btc,
@@ -452,6 +750,23 @@ libfuzzer_sys::fuzz_target!(|method: Method| {
                 borrow_fee,
                 take_cash_fee,
                 liquidation_reward,
+                collateral_cap,
+                liquidity_cap,
+                debt_cap,
+                reserve_factor,
+                close_factor,
+                max_liquidation_reward,
+                auction_duration,
+                balance_delta,
+                max_price_age,
+                price_mode: match twap {
+                    Some(true) => PriceMode::Twap,
+                    Some(false) => PriceMode::Conservative,
+                    None => PriceMode::Spot,
+                },
+                twap_window,
+                isolated,
+                isolated_debt_ceiling,
             };
             let _ = contract.set_params(params);
         },
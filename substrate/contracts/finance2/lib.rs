@@ -3,9 +3,12 @@
 pub mod logic;
 pub mod errors;
 pub mod structs;
+pub mod events;
+pub mod migration;
 
-pub use self::finance2::LAssetContractRef;
+pub use self::finance2::{LAssetContract, LAssetContractRef};
 
+#[allow(clippy::large_enum_variant)]
 #[ink::contract]
 mod finance2 {
     use ink::prelude::vec::Vec;
@@ -14,22 +17,34 @@ mod finance2 {
     use crate::errors::TakeCashError;
     use crate::logic::{require, add, mulw, sub};
     use crate::errors::LAssetError;
-    use crate::structs::{AssetParams, AssetPool, LAsset, UpdateOrRepayResult, UpdateResult};
-    use ink::storage::Mapping;
+    use crate::events::{Accrue, Borrow, Burn, Delegation, Deposit, DepositCash, DepositorApproval, Flag, Liquidate, Migrate, Mint, Relink, Repay, SetCategory, SetParams, SetPaused, SetPrice, SetStatus, Skim, Sync, TakeCash, Withdraw, WithdrawCash, WithdrawReserve, WriteOff};
+    use crate::structs::{AccountSnapshot, AssetParams, AssetPool, AssetStatus, Category, CategoryCheck, LAsset, PauseFlags, PriceMode, UpdateOrRepayResult, UpdateResult};
+    use ink::storage::{Lazy, Mapping};
+    use ink::storage::traits::StorageKey;
+    use crate::migration::append_fields;
+
+    /// Bumped with each change to the stored layout, new root fields go behind `Lazy`
+    pub const STORAGE_VERSION: u32 = 6;
 
     #[ink(storage)]
     pub struct LAssetContract {
+        pub version: u32,
         pub admin: AccountId,
         pub underlying_token: AccountId,
+        pub native: bool,
         pub last_updated_at: Timestamp,
 
         pub next: AccountId,
 
         pub total_collateral: u128,
         pub collateral: Mapping<AccountId, u128>,
+        pub depositors: Mapping<(AccountId, AccountId), ()>,
+        pub flagged: Mapping<AccountId, Timestamp>,
+        pub healthy_at: Mapping<AccountId, Timestamp>,
     
         pub last_total_liquidity: u128,
         pub total_borrowable: u128,
+        pub total_reserve: u128,
     
         pub total_shares: u128,
         pub shares: Mapping<AccountId, u128>,
@@ -37,12 +52,23 @@ mod finance2 {
     
         pub total_bonds: u128,
         pub bonds: Mapping<AccountId, u128>,
+        pub delegation: Mapping<(AccountId, AccountId), u128>,
+        pub total_isolated_bonds: Lazy<u128>,
+        pub isolated_bonds: Mapping<AccountId, u128>,
 
-        pub params: AssetParams,
+        pub params: Lazy<AssetParams>,
+        pub paused: PauseFlags,
+        pub status: Lazy<AssetStatus>,
+        pub category: Lazy<u32>,
+        pub category_params: Lazy<Category>,
 
         pub price: u128,
+        pub debt_price: Lazy<u128>,
+        pub category_price: Lazy<u128>,
         pub price_scaler: u128,
+        pub price_updated_at: Lazy<Timestamp>,
 
+        pub total_cash: u128,
         pub cash: Mapping<AccountId, u128>,
         pub whitelist: Mapping<AccountId, AccountId>,
 
@@ -63,38 +89,108 @@ mod finance2 {
             gas_collateral: u128,
         ) -> Self {
             let (name, symbol, decimals) = fetch_psp22_metadata(underlying_token);
+            Self::init(underlying_token, false, next, gas_collateral, name, symbol, decimals)
+        }
+
+        /// Pool lending the chain's native currency instead of a PSP22 token.
+        /// The underlying is taken from the transferred value and the pool itself stands in as `underlying_token`.
+        #[ink(constructor)]
+        pub fn new_native(
+            next: AccountId,
+            gas_collateral: u128,
+            decimals: u8,
+        ) -> Self {
+            let this = Self::env().account_id();
+            Self::init(this, true, next, gas_collateral, None, None, decimals)
+        }
+
+        fn init(
+            underlying_token: AccountId,
+            native: bool,
+            next: AccountId,
+            gas_collateral: u128,
+            name: Option<String>,
+            symbol: Option<String>,
+            decimals: u8,
+        ) -> Self {
             let admin: AccountId = Self::env().caller();
+            let mut params = Lazy::new();
+            params.set(&AssetParams {
+                standard_rate: 0,
+                standard_min_rate: 0,
+                emergency_rate: 0,
+                emergency_max_rate: 0,
+                initial_margin: 0,
+                maintenance_margin: 0,
+                initial_haircut: u128::MAX,
+                maintenance_haircut: u128::MAX,
+                mint_fee: 0,
+                borrow_fee: 0,
+                take_cash_fee: 0,
+                liquidation_reward: 0,
+                collateral_cap: u128::MAX,
+                liquidity_cap: u128::MAX,
+                debt_cap: u128::MAX,
+                reserve_factor: 0,
+                close_factor: u128::MAX,
+                max_liquidation_reward: 0,
+                auction_duration: 0,
+                balance_delta: false,
+                max_price_age: 0,
+                price_mode: PriceMode::Spot,
+                twap_window: 0,
+                isolated: false,
+                isolated_debt_ceiling: 0,
+            });
+            let mut status = Lazy::new();
+            status.set(&AssetStatus::Active);
+            let mut total_isolated_bonds = Lazy::new();
+            total_isolated_bonds.set(&0);
+            let mut category = Lazy::new();
+            category.set(&0);
+            let mut category_params = Lazy::new();
+            category_params.set(&Category::default());
+            let mut debt_price = Lazy::new();
+            debt_price.set(&0);
+            let mut category_price = Lazy::new();
+            category_price.set(&0);
+            let mut price_updated_at = Lazy::new();
+            price_updated_at.set(&0);
 
             Self { 
+                version: STORAGE_VERSION,
                 admin,
                 underlying_token,
+                native,
                 last_updated_at: Self::env().block_timestamp(),
                 next,
                 total_collateral: 0,
                 collateral: Mapping::new(),
+                depositors: Mapping::new(),
+                flagged: Mapping::new(),
+                healthy_at: Mapping::new(),
                 last_total_liquidity: 0,
+                total_reserve: 0,
                 total_shares: 0,
                 shares: Mapping::new(),
                 allowance: Mapping::new(),
                 total_borrowable: 0,
                 total_bonds: 0,
                 bonds: Mapping::new(),
-                params: AssetParams {
-                    standard_rate: 0,
-                    standard_min_rate: 0,
-                    emergency_rate: 0,
-                    emergency_max_rate: 0,
-                    initial_margin: 0,
-                    maintenance_margin: 0,
-                    initial_haircut: u128::MAX,
-                    maintenance_haircut: u128::MAX,
-                    mint_fee: 0,
-                    borrow_fee: 0,
-                    take_cash_fee: 0,
-                    liquidation_reward: 0,
-                },
+                delegation: Mapping::new(),
+                total_isolated_bonds,
+                isolated_bonds: Mapping::new(),
+                params,
+                paused: PauseFlags::default(),
+                status,
+                category,
+                category_params,
                 price: 0,
+                debt_price,
+                category_price,
                 price_scaler: 1,
+                price_updated_at,
+                total_cash: 0,
                 cash: Mapping::new(),
                 whitelist: Mapping::new(),
                 name,
@@ -104,116 +200,270 @@ mod finance2 {
              }
         }
 
-        #[ink(message)]
+        #[ink(message, payable)]
         pub fn deposit(&mut self, to_deposit: u128) -> Result<(), LAssetError> {
             let caller = self.env().caller();
-            let this = self.env().account_id();
-            transfer_from(self.underlying_token, caller, this, to_deposit).map_err(LAssetError::DepositTransferFailed)?;
+            self.inner_deposit(caller, caller, to_deposit)
+        }
 
-            let collateral = if let Some(c) = self.collateral.get(caller) {
-                Ok(c)
-            } else if self.bonds.contains(caller) {
-                Err(LAssetError::DepositWhileBorrowingNotAllowed)
-            } else if self.env().transferred_value() != self.gas_collateral {
+        /// Collateral can change how the user's account is valued (isolation, categories), so the user has to approve the depositor
+        #[ink(message, payable)]
+        pub fn deposit_for(&mut self, user: AccountId, to_deposit: u128) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            require(caller == user || self.depositors.contains((user, caller)), LAssetError::DepositForNotApproved)?;
+            self.inner_deposit(caller, user, to_deposit)
+        }
+
+        #[ink(message)]
+        pub fn approve_depositor(&mut self, depositor: AccountId, approved: bool) -> Result<(), LAssetError> {
+            let user = self.env().caller();
+            if approved {
+                self.depositors.insert((user, depositor), &());
+            } else {
+                self.depositors.remove((user, depositor));
+            }
+
+            self.env().emit_event(DepositorApproval {user, depositor, approved});
+            Ok(())
+        }
+
+        fn inner_deposit(&mut self, payer: AccountId, user: AccountId, to_deposit: u128) -> Result<(), LAssetError> {
+            require(!self.paused.deposit, LAssetError::DepositPaused)?;
+            require(self.status() == AssetStatus::Active, LAssetError::DepositReduceOnly)?;
+            let (collateral, gas) = if let Some(c) = self.collateral.get(user) {
+                Ok((c, 0))
+            } else if self.bonds.contains(user) {
+                Ok((0, 0))
+            } else if self.gas_value(to_deposit) != self.gas_collateral {
                 Err(LAssetError::FirstDepositRequiresGasCollateral)
             } else {
-                Ok(0)
+                Ok((0, self.gas_collateral))
             }?;
-            let new_total_collateral = self.total_collateral.checked_add(to_deposit).ok_or(LAssetError::DepositOverflow)?;
-            let new_collateral = add(collateral, to_deposit); //PROVED
+            let deposited = self.transfer_in(payer, to_deposit, gas).map_err(LAssetError::DepositTransferFailed)?;
+
+            let new_total_collateral = self.total_collateral.checked_add(deposited).ok_or(LAssetError::DepositOverflow)?;
+            require(new_total_collateral <= self.params().collateral_cap, LAssetError::DepositCapExceeded)?;
+            let new_collateral = add(collateral, deposited); //PROVED
             
             self.total_collateral = new_total_collateral;
-            self.collateral.insert(caller, &new_collateral);
-            
+            self.collateral.insert(user, &new_collateral);
+
+            self.env().emit_event(Deposit {user, payer, amount: deposited, total_collateral: new_total_collateral});
             Ok(())
         }
         
         #[ink(message)]
         pub fn withdraw(&mut self, to_withdraw: u128) -> Result<(), LAssetError> {
             let caller = self.env().caller();
-            
+            self.inner_withdraw(caller, caller, to_withdraw)
+        }
+
+        #[ink(message)]
+        pub fn withdraw_to(&mut self, recipient: AccountId, to_withdraw: u128) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            self.inner_withdraw(caller, recipient, to_withdraw)
+        }
+
+        fn inner_withdraw(&mut self, caller: AccountId, recipient: AccountId, to_withdraw: u128) -> Result<(), LAssetError> {
+            require(!self.paused.withdraw, LAssetError::WithdrawPaused)?;
+            require(self.status() != AssetStatus::Removed, LAssetError::WithdrawRemoved)?;
+            require(!self.is_price_stale(), LAssetError::WithdrawStalePrice(self.env().account_id()))?;
             let collateral = self.collateral.get(caller).ok_or(LAssetError::WithdrawWithoutDeposit)?;
             let new_collateral = collateral.checked_sub(to_withdraw).ok_or(LAssetError::WithdrawOverflow)?;
             let new_total_collateral = sub(self.total_collateral, to_withdraw); //PROVED
 
-            let mut total_icv = if let Some(qouted_collateral) = mulw(new_collateral, self.price).div(self.price_scaler) {
-                mulw(qouted_collateral, self.params.initial_haircut).scale()
-            } else {
-                u128::MAX
-            };
-            let mut total_idv: u128 = 0;
+            let (mut total_icv, _, mut total_cicv, _) = self.collateral_values(new_collateral);
+            let (mut total_idv, _, mut total_cidv, _) = self.accrued_debt_values(caller);
+            let mut categories = CategoryCheck::default();
+            categories.position(Some(self.category()));
 
+            let params = self.params();
+            let mut collateral_pools = u32::from(new_collateral != 0);
+            let mut isolated = new_collateral != 0 && params.isolated;
+            let mut non_isolated_debt = self.has_non_isolated_debt(caller, &params);
+
+            let mut stale = None;
             let mut next = self.next;
             let this = self.env().account_id();
             while next != this {
                 let result = update_next(&next, &caller);
+                if result.stale_price && stale.is_none() {
+                    stale = Some(next);
+                }
                 next = result.next;
                 total_icv = total_icv.saturating_add(result.initial_collateral_value);
                 total_idv = total_idv.saturating_add(result.initial_debt_value);
+                total_cicv = total_cicv.saturating_add(result.category_initial_collateral_value);
+                total_cidv = total_cidv.saturating_add(result.category_initial_debt_value);
+                categories.position(result.category);
+                categories.user(result.user_category);
+                collateral_pools = collateral_pools.saturating_add(u32::from(result.has_collateral));
+                isolated = isolated || result.has_isolated_collateral;
+                non_isolated_debt = non_isolated_debt || result.has_non_isolated_debt;
+            }
+            if let Some(pool) = stale {
+                return Err(LAssetError::WithdrawStalePrice(pool));
             }
+            let (total_icv, total_idv) = if categories.applies() { (total_cicv, total_cidv) } else { (total_icv, total_idv) };
             require(total_idv == 0 || total_icv > total_idv, LAssetError::CollateralValueTooLowAfterWithdraw)?;
+            if isolated && total_idv != 0 { // the debt left must satisfy the same isolation rules as a new borrow
+                require(collateral_pools == 1, LAssetError::WithdrawIsolatedWithOtherCollateral)?;
+                require(!non_isolated_debt, LAssetError::WithdrawNotAllowedInIsolation)?;
+            }
 
+            self.healthy_at.insert(caller, &self.env().block_timestamp());
             self.total_collateral = new_total_collateral;
             if new_collateral != 0 {
                 self.collateral.insert(caller, &new_collateral);
             } else {
                 self.collateral.remove(caller);
-                self.transfer_gas(caller);
+                self.flagged.remove(caller);
+                if !self.bonds.contains(caller) {
+                    self.transfer_gas(caller);
+                }
             }
 
-            transfer(self.underlying_token, caller, to_withdraw).map_err(LAssetError::WithdrawTransferFailed)
+            self.env().emit_event(Withdraw {user: caller, recipient, amount: to_withdraw, total_collateral: new_total_collateral});
+            self.transfer_out(recipient, to_withdraw).map_err(LAssetError::WithdrawTransferFailed)
         }
 
         fn transfer_gas(&self, to: AccountId) {
             let _ = self.env().transfer(to, self.gas_collateral); //should never fail
         }
 
-        #[ink(message)]
+        fn gas_value(&self, amount: u128) -> u128 {
+            let value = self.env().transferred_value();
+            if self.native {
+                value.saturating_sub(amount)
+            } else {
+                value
+            }
+        }
+
+        /// Value sent along has to be exactly `gas`, plus `amount` in native pools, anything else would stay unaccounted
+        fn transfer_in(&self, from: AccountId, amount: u128, gas: u128) -> Result<u128, PSP22Error> {
+            let this = self.env().account_id();
+            if self.native {
+                let expected = amount.checked_add(gas);
+                require(expected == Some(self.env().transferred_value()), PSP22Error::Custom("TransferredValueMismatch".to_string()))?;
+                return Ok(amount);
+            }
+            require(self.env().transferred_value() == gas, PSP22Error::Custom("TransferredValueMismatch".to_string()))?;
+            if self.params().balance_delta {
+                let balance = balance_of(self.underlying_token, this);
+                transfer_from(self.underlying_token, from, this, amount)?;
+                let new_balance = balance_of(self.underlying_token, this);
+                Ok(new_balance.saturating_sub(balance).min(amount))
+            } else {
+                transfer_from(self.underlying_token, from, this, amount)?;
+                Ok(amount)
+            }
+        }
+
+        fn surplus(&self) -> Result<u128, u128> {
+            let balance = balance_of(self.underlying_token, self.env().account_id());
+            let accounted = self.total_collateral.saturating_add(self.total_borrowable).saturating_add(self.total_cash);
+            if balance >= accounted {
+                Ok(sub(balance, accounted)) //PROVED
+            } else {
+                Err(sub(accounted, balance)) //PROVED
+            }
+        }
+
+        fn transfer_out(&self, to: AccountId, amount: u128) -> Result<(), PSP22Error> {
+            if self.native {
+                self.env().transfer(to, amount).map_err(|_| PSP22Error::Custom("NativeTransferFailed".to_string()))
+            } else {
+                transfer(self.underlying_token, to, amount)
+            }
+        }
+
+        #[ink(message, payable)]
         pub fn mint(&mut self, to_wrap: u128) -> Result<(), LAssetError> {
             let caller = self.env().caller();
-            let this = self.env().account_id();
+            self.inner_mint(caller, caller, to_wrap)
+        }
+
+        #[ink(message, payable)]
+        pub fn mint_to(&mut self, recipient: AccountId, to_wrap: u128) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            self.inner_mint(caller, recipient, to_wrap)
+        }
 
-            let fee = mulw(to_wrap, self.params.mint_fee).scale_up();
+        fn inner_mint(&mut self, caller: AccountId, recipient: AccountId, to_wrap: u128) -> Result<(), LAssetError> {
+            require(!self.paused.mint, LAssetError::MintPaused)?;
+            require(self.status() == AssetStatus::Active, LAssetError::MintReduceOnly)?;
+            let params = self.params();
+            let fee = mulw(to_wrap, params.mint_fee).scale_up();
             let to_transfer = to_wrap.checked_add(fee).ok_or(LAssetError::MintFeeOverflow)?;
             
-            transfer_from(self.underlying_token, caller, this, to_transfer).map_err(LAssetError::MintTransferFailed)?;
+            let received = self.transfer_in(caller, to_transfer, 0).map_err(LAssetError::MintTransferFailed)?;
+            let wrapped = received.saturating_sub(fee);
 
             let total_borrowable = self.total_borrowable;
-            let (total_liquidity, updated_at) = self.inner_accrue(total_borrowable);
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(total_borrowable);
 
             let total_shares = self.total_shares;
-            let shares = self.shares.get(caller).unwrap_or(0);
+            let shares = self.shares.get(recipient).unwrap_or(0);
             
-            let new_total_liquidity = total_liquidity.checked_add(to_transfer).ok_or(LAssetError::MintOverflow)?;
-            let new_total_borrowable = add(total_borrowable, to_transfer); //PROVED
+            let new_total_liquidity = total_liquidity.checked_add(received).ok_or(LAssetError::MintOverflow)?;
+            require(new_total_liquidity <= params.liquidity_cap, LAssetError::MintCapExceeded)?;
+            let new_total_borrowable = add(total_borrowable, received); //PROVED
+            let fee_reserve = mulw(fee, params.reserve_factor).scale();
+            let new_total_reserve = add(total_reserve, fee_reserve); //PROVED
             
-            let to_mint = mulw(to_wrap, total_shares).div_rate(total_liquidity).unwrap_or(to_transfer); //PROVED
+            let supplied = sub(total_liquidity, total_reserve); //PROVED
+            let to_mint = mulw(wrapped, total_shares).div_rate(supplied).unwrap_or(received); //PROVED
+            require(to_mint != 0, LAssetError::MintForNothing)?;
             let new_total_shares = add(total_shares, to_mint); //PROVED
             let new_shares = add(shares, to_mint); //PROVED
 
             self.total_shares = new_total_shares;
-            self.shares.insert(caller, &new_shares);
+            self.shares.insert(recipient, &new_shares);
             
             self.total_borrowable = new_total_borrowable;
             self.last_total_liquidity = new_total_liquidity;
+            self.total_reserve = new_total_reserve;
             self.last_updated_at = updated_at;
             
-            self.env().emit_event(Transfer {from: None, to: Some(caller), value: to_mint});
+            self.env().emit_event(Transfer {from: None, to: Some(recipient), value: to_mint});
+            self.env().emit_event(Mint {
+                user: recipient,
+                payer: caller,
+                amount: wrapped,
+                fee,
+                shares: to_mint,
+                total_shares: new_total_shares,
+                total_liquidity: new_total_liquidity,
+                total_borrowable: new_total_borrowable,
+                total_reserve: new_total_reserve,
+            });
             Ok(())
         }
 
         #[ink(message)]
         pub fn burn(&mut self, to_burn: u128) -> Result<(), LAssetError> {
             let caller = self.env().caller();
+            self.inner_burn(caller, caller, to_burn)
+        }
 
+        #[ink(message)]
+        pub fn burn_to(&mut self, recipient: AccountId, to_burn: u128) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            self.inner_burn(caller, recipient, to_burn)
+        }
+
+        fn inner_burn(&mut self, caller: AccountId, recipient: AccountId, to_burn: u128) -> Result<(), LAssetError> {
+            require(!self.paused.burn, LAssetError::BurnPaused)?;
             let total_borrowable = self.total_borrowable;
-            let (total_liquidity, updated_at) = self.inner_accrue(total_borrowable);
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(total_borrowable);
 
             let total_shares = self.total_shares;
             let shares = self.shares.get(caller).unwrap_or(0);
 
             let new_shares = shares.checked_sub(to_burn).ok_or(LAssetError::BurnOverflow)?;
-            let to_withdraw = mulw(to_burn, total_liquidity).div_rate(total_shares).unwrap_or(0); //PROVED
+            let supplied = sub(total_liquidity, total_reserve); //PROVED
+            let to_withdraw = mulw(to_burn, supplied).div_rate(total_shares).unwrap_or(0); //PROVED
             let new_total_borrowable = total_borrowable.checked_sub(to_withdraw).ok_or(LAssetError::BurnTooMuch)?;
             let new_total_shares = sub(total_shares, to_burn); //PROVED
             let new_total_liquidity = sub(total_liquidity, to_withdraw); //PROVED
@@ -223,77 +473,178 @@ mod finance2 {
             
             self.total_borrowable = new_total_borrowable;
             self.last_total_liquidity = new_total_liquidity;
+            self.total_reserve = total_reserve;
             self.last_updated_at = updated_at;
 
             self.env().emit_event(Transfer {from: Some(caller), to: None, value: to_burn});
-
-            transfer(self.underlying_token, caller, to_withdraw).map_err(LAssetError::BurnTransferFailed)
+            self.env().emit_event(Burn {
+                user: caller,
+                recipient,
+                amount: to_withdraw,
+                shares: to_burn,
+                total_shares: new_total_shares,
+                total_liquidity: new_total_liquidity,
+                total_borrowable: new_total_borrowable,
+            });
+
+            self.transfer_out(recipient, to_withdraw).map_err(LAssetError::BurnTransferFailed)
         }
 
-        #[ink(message)]
+        #[ink(message, payable)]
         pub fn borrow(&mut self, to_borrow: u128) -> Result<(), LAssetError> {
             let caller = self.env().caller();
+            self.inner_borrow(caller, caller, to_borrow)
+        }
+
+        #[ink(message, payable)]
+        pub fn borrow_on_behalf(&mut self, owner: AccountId, to_borrow: u128) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            if caller != owner {
+                let delegation = self.delegation.get((owner, caller)).unwrap_or(0);
+                let new_delegation = delegation.checked_sub(to_borrow).ok_or(LAssetError::BorrowOnBehalfNotDelegated)?;
+
+                self.delegation.insert((owner, caller), &new_delegation);
+                self.env().emit_event(Delegation {owner, delegatee: caller, amount: new_delegation});
+            }
+            self.inner_borrow(owner, caller, to_borrow)
+        }
+
+        #[ink(message)]
+        pub fn approve_delegation(&mut self, delegatee: AccountId, amount: u128) -> Result<(), LAssetError> {
+            let owner = self.env().caller();
+            self.delegation.insert((owner, delegatee), &amount);
+
+            self.env().emit_event(Delegation {owner, delegatee, amount});
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn delegation(&self, owner: AccountId, delegatee: AccountId) -> u128 {
+            self.delegation.get((owner, delegatee)).unwrap_or(0)
+        }
+
+        fn inner_borrow(&mut self, user: AccountId, recipient: AccountId, to_borrow: u128) -> Result<(), LAssetError> {
+            require(!self.paused.borrow, LAssetError::BorrowPaused)?;
+            let status = self.status();
+            require(status != AssetStatus::Removed, LAssetError::BorrowRemoved)?;
+            require(status == AssetStatus::Active, LAssetError::BorrowReduceOnly)?;
             let this = self.env().account_id();
+            require(!self.is_price_stale(), LAssetError::BorrowStalePrice(this))?;
 
             let total_borrowable = self.total_borrowable;
-            let (total_liquidity, updated_at) = self.inner_accrue(total_borrowable);
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(total_borrowable);
 
-            let bonds = if let Some(b) = self.bonds.get(caller) {
-                Ok(b)
-            } else if self.collateral.contains(caller) {
-                Err(LAssetError::BorrowWhileDepositingNotAllowed)
+            let (bonds, gas) = if let Some(b) = self.bonds.get(user) {
+                Ok((b, 0))
+            } else if self.collateral.contains(user) {
+                Ok((0, 0))
             } else if self.env().transferred_value() != self.gas_collateral {
                 Err(LAssetError::FirstBorrowRequiresGasCollateral)
             } else {
-                Ok(0)
+                Ok((0, self.gas_collateral))
             }?;
+            require(self.env().transferred_value() == gas, LAssetError::BorrowUnexpectedValue)?;
 
-            let fee = mulw(to_borrow, self.params.borrow_fee).scale_up();
+            let params = self.params();
+            let fee = mulw(to_borrow, params.borrow_fee).scale_up();
             let to_return = to_borrow.checked_add(fee).ok_or(LAssetError::BorrowFeeOverflow)?;
-            let new_total_borrowable = total_borrowable.checked_sub(to_return).ok_or(LAssetError::BorrowOverflow)?;
+            let new_total_borrowable = total_borrowable.checked_sub(to_borrow).ok_or(LAssetError::BorrowOverflow)?;
+            let new_total_liquidity = total_liquidity.checked_add(fee).ok_or(LAssetError::BorrowFeeOverflow)?;
+            let fee_reserve = mulw(fee, params.reserve_factor).scale();
+            let new_total_reserve = add(total_reserve, fee_reserve); //PROVED
             let total_debt = sub(total_liquidity, total_borrowable); //PROVED
             let total_bonds = self.total_bonds;
             let to_mint = mulw(to_return, total_bonds).ceil_rate(total_debt).unwrap_or(to_return); //PROVED
             let new_total_bonds = add(total_bonds, to_mint); //PROVED
             let new_bonds = add(bonds, to_mint); //PROVED
             
-            let new_total_debt = sub(total_liquidity, new_total_borrowable); //PROVED
+            let new_total_debt = sub(new_total_liquidity, new_total_borrowable); //PROVED
+            require(new_total_debt <= params.debt_cap, LAssetError::BorrowCapExceeded)?;
             let debt = mulw(new_bonds, new_total_debt).ceil_rate(new_total_bonds).unwrap_or(new_total_debt); //PROVED
-            let quoted_debt = mulw(debt, self.price).ceil_up(self.price_scaler).unwrap_or(u128::MAX);
-            let mut total_idv = mulw(quoted_debt, self.params.initial_margin).scale_up().saturating_add(quoted_debt);
-            let mut total_icv: u128 = 0;
+            let (mut total_idv, _, mut total_cidv, _) = self.quoted_debt_values(debt);
+            let collateral = self.collateral.get(user);
+            let (mut total_icv, _, mut total_cicv, _) = collateral.map(|c| self.collateral_values(c)).unwrap_or((0, 0, 0, 0));
+            let mut categories = CategoryCheck::default();
+            categories.position(Some(self.category()));
 
+            let mut collateral_pools = u32::from(collateral.is_some());
+            let mut isolated = collateral.is_some() && params.isolated;
+            let mut non_isolated_debt = self.has_non_isolated_debt(user, &params);
+
+            let mut stale = None;
             let mut next = self.next;
             while next != this {
-                let result = update_next(&next, &caller);
+                let result = update_next(&next, &user);
+                if result.stale_price && stale.is_none() {
+                    stale = Some(next);
+                }
                 next = result.next;
                 total_icv = total_icv.saturating_add(result.initial_collateral_value);
                 total_idv = total_idv.saturating_add(result.initial_debt_value);
+                total_cicv = total_cicv.saturating_add(result.category_initial_collateral_value);
+                total_cidv = total_cidv.saturating_add(result.category_initial_debt_value);
+                categories.position(result.category);
+                categories.user(result.user_category);
+                collateral_pools = collateral_pools.saturating_add(u32::from(result.has_collateral));
+                isolated = isolated || result.has_isolated_collateral;
+                non_isolated_debt = non_isolated_debt || result.has_non_isolated_debt;
             }
+            if let Some(pool) = stale {
+                return Err(LAssetError::BorrowStalePrice(pool));
+            }
+            let (total_icv, total_idv) = if categories.applies() { (total_cicv, total_cidv) } else { (total_icv, total_idv) };
             require(total_icv > total_idv, LAssetError::CollateralValueTooLowAfterBorrow)?;
 
+            if isolated {
+                require(collateral_pools == 1, LAssetError::BorrowIsolatedWithOtherCollateral)?;
+                require(!non_isolated_debt && params.isolated_debt_ceiling != 0, LAssetError::BorrowNotAllowedInIsolation)?;
+
+                let new_total_isolated_bonds = add(self.total_isolated_bonds.get().unwrap_or(0), to_mint); //PROVED
+                let isolated_debt = mulw(new_total_isolated_bonds, new_total_debt).ceil_rate(new_total_bonds).unwrap_or(new_total_debt); //PROVED
+                require(isolated_debt <= params.isolated_debt_ceiling, LAssetError::BorrowIsolatedCeilingExceeded)?;
+
+                let isolated_bonds = self.isolated_bonds.get(user).unwrap_or(0);
+                self.total_isolated_bonds.set(&new_total_isolated_bonds);
+                self.isolated_bonds.insert(user, &add(isolated_bonds, to_mint)); //PROVED
+            }
+
             self.total_bonds = new_total_bonds;
-            self.bonds.insert(caller, &new_bonds);
+            self.bonds.insert(user, &new_bonds);
+            self.healthy_at.insert(user, &updated_at);
             
             self.total_borrowable = new_total_borrowable;
-            self.last_total_liquidity = total_liquidity;
+            self.last_total_liquidity = new_total_liquidity;
+            self.total_reserve = new_total_reserve;
             self.last_updated_at = updated_at;
 
-            transfer(self.underlying_token, caller, to_borrow).map_err(LAssetError::BorrowTransferFailed)
+            self.env().emit_event(Borrow {
+                user,
+                recipient,
+                amount: to_borrow,
+                fee,
+                bonds: to_mint,
+                total_bonds: new_total_bonds,
+                total_liquidity: new_total_liquidity,
+                total_borrowable: new_total_borrowable,
+                total_reserve: new_total_reserve,
+            });
+            self.transfer_out(recipient, to_borrow).map_err(LAssetError::BorrowTransferFailed)
         }
 
-        #[ink(message)]
+        #[ink(message, payable)]
         pub fn deposit_cash(&mut self, spender: AccountId, extra_cash: u128) -> Result<(), LAssetError> {
             let caller = self.env().caller();
-            let this = self.env().account_id();
-            transfer_from(self.underlying_token, caller, this, extra_cash).map_err(LAssetError::DepositCashTransferFailed)?;
+            let received = self.transfer_in(caller, extra_cash, 0).map_err(LAssetError::DepositCashTransferFailed)?;
             
             let cash = self.cash.get(caller).unwrap_or(0);
-            let new_cash = cash.checked_add(extra_cash).ok_or(LAssetError::DepositCashOverflow)?;
+            let new_total_cash = self.total_cash.checked_add(received).ok_or(LAssetError::DepositCashOverflow)?;
+            let new_cash = add(cash, received); //PROVED
 
+            self.total_cash = new_total_cash;
             self.cash.insert(caller, &new_cash);
             self.whitelist.insert(caller, &spender);
 
+            self.env().emit_event(DepositCash {owner: caller, spender, amount: received, cash: new_cash});
             Ok(())
         }
 
@@ -302,54 +653,118 @@ mod finance2 {
             let caller = self.env().caller();
             let cash = self.cash.get(caller).unwrap_or(0);
             
+            self.total_cash = sub(self.total_cash, cash); //PROVED
             self.cash.remove(caller);
 
-            transfer(self.underlying_token, caller, cash).map_err(LAssetError::WithdrawCashTransferFailed)
+            self.env().emit_event(WithdrawCash {owner: caller, amount: cash});
+            self.transfer_out(caller, cash).map_err(LAssetError::WithdrawCashTransferFailed)
         }
 
         #[ink(message)]
         pub fn liquidate(&mut self, user: AccountId) -> Result<(), LAssetError> {
+            require(!self.paused.liquidate, LAssetError::LiquidatePaused)?;
+            require(self.status() != AssetStatus::Removed, LAssetError::LiquidateRemoved)?;
             let caller = self.env().caller();
             let this = self.env().account_id();
+            require(!self.is_price_stale(), LAssetError::LiquidateStalePrice(this))?;
 
+            let result = self.inner_repay_or_update(this, user, caller);
             let mut total_icv: u128 = 0;
-            let mut total_idv: u128 = 0;
+            let mut total_idv = result.initial_debt_value;
             let mut total_mcv: u128 = 0;
-            let mut total_mdv: u128 = 0;
-            let mut total_repaid: u128 = 0;
+            let mut total_mdv = result.maintenance_debt_value;
+            let mut total_cicv: u128 = 0;
+            let mut total_cidv = result.category_initial_debt_value;
+            let mut total_cmcv: u128 = 0;
+            let mut total_cmdv = result.category_maintenance_debt_value;
+            let mut total_repaid = result.qouted_repaid;
+            let mut debt_pools = Vec::new();
+            if result.qouted_repaid != 0 {
+                debt_pools.push((this, result.qouted_repaid));
+            }
+            let mut healthy_at = result.healthy_at;
+            let mut categories = CategoryCheck::default();
+            categories.position(result.category);
 
+            let mut stale = None;
             let mut next = self.next;
             while next != this {
                 let result = repay_or_update(next, user, caller);
+                if result.stale_price && stale.is_none() {
+                    stale = Some(next);
+                }
+                if result.qouted_repaid != 0 {
+                    debt_pools.push((next, result.qouted_repaid));
+                }
                 
                 next = result.next;
+                healthy_at = healthy_at.max(result.healthy_at);
                 total_repaid = total_repaid.saturating_add(result.qouted_repaid);
                 total_icv = total_icv.saturating_add(result.initial_collateral_value);
                 total_idv = total_idv.saturating_add(result.initial_debt_value);
                 total_mcv = total_mcv.saturating_add(result.maintenance_collateral_value);
                 total_mdv = total_mdv.saturating_add(result.maintenance_debt_value);
+                total_cicv = total_cicv.saturating_add(result.category_initial_collateral_value);
+                total_cidv = total_cidv.saturating_add(result.category_initial_debt_value);
+                total_cmcv = total_cmcv.saturating_add(result.category_maintenance_collateral_value);
+                total_cmdv = total_cmdv.saturating_add(result.category_maintenance_debt_value);
+                categories.position(result.category);
+                categories.user(result.user_category);
             }
 
+            if let Some(pool) = stale {
+                return Err(LAssetError::LiquidateStalePrice(pool));
+            }
             let collateral = self.collateral.get(user).ok_or(LAssetError::LiquidateForNothing)?;
 
             let price = self.price;
             let price_scaler = self.price_scaler;
+
+            let (icv, mcv, cicv, cmcv) = self.collateral_values(collateral);
+            let in_category = categories.applies();
+            let (total_icv, total_idv) = if in_category {
+                (total_cicv.saturating_add(cicv), total_cidv)
+            } else {
+                (total_icv.saturating_add(icv), total_idv)
+            };
+            let (underwater_mcv, underwater_mdv) = if in_category {
+                (total_cmcv.saturating_add(cmcv), total_cmdv)
+            } else {
+                (total_mcv.saturating_add(mcv), total_mdv)
+            };
+
+            let params = self.params();
+            let reward_rate = if params.auction_duration != 0 {
+                let flagged_at = self.flagged.get(user).ok_or(LAssetError::LiquidateNotFlagged)?;
+                let flagged_at = if flagged_at < healthy_at { // found healthy since, the auction starts over
+                    let now = self.env().block_timestamp();
+                    self.flagged.insert(user, &now);
+                    now
+                } else {
+                    flagged_at
+                };
+                let elapsed = (self.env().block_timestamp().saturating_sub(flagged_at) as u128).min(params.auction_duration);
+                mulw(params.max_liquidation_reward, elapsed).div(params.auction_duration).unwrap_or(0)
+            } else {
+                let reward_rate = mulw(params.liquidation_reward, underwater_mdv).div(underwater_mcv).unwrap_or(u128::MAX);
+                if params.max_liquidation_reward == 0 { // no cap
+                    reward_rate
+                } else {
+                    reward_rate.min(params.max_liquidation_reward)
+                }
+            };
             let repaid_collateral = mulw(total_repaid, price_scaler).div(price).unwrap_or(u128::MAX);
-            let rewards = mulw(repaid_collateral, self.params.liquidation_reward).scale_up();
+            let rewards = mulw(repaid_collateral, reward_rate).scale_up();
             let to_take = repaid_collateral.saturating_add(rewards).min(collateral);
 
             let new_collateral = sub(collateral, to_take); //PROVED
             let new_total_collateral = sub(self.total_collateral, to_take); //PROVED
 
-            total_icv = if let Some(qouted_collateral) = mulw(collateral, price).div(price_scaler) {
-                mulw(qouted_collateral, self.params.initial_haircut).scale().saturating_add(total_icv)
+            let (_, mcv, _, cmcv) = self.collateral_values(new_collateral);
+            let (total_mcv, total_mdv) = if in_category {
+                (total_cmcv.saturating_add(cmcv), total_cmdv)
             } else {
-                u128::MAX
-            };
-            total_mcv = if let Some(qouted_new_collateral) = mulw(new_collateral, price).div(price_scaler) {
-                mulw(qouted_new_collateral, self.params.maintenance_haircut).scale().saturating_add(total_mcv)
-            } else {
-                u128::MAX
+                (total_mcv.saturating_add(mcv), total_mdv)
             };
 
             require(total_mdv > total_mcv, LAssetError::LiquidateTooEarly)?;
@@ -360,10 +775,125 @@ mod finance2 {
                 self.collateral.insert(user, &new_collateral);
             } else {
                 self.collateral.remove(user);
-                self.transfer_gas(caller);
+                self.flagged.remove(user);
+                if !self.bonds.contains(user) {
+                    self.transfer_gas(caller);
+                }
+            }
+
+            self.env().emit_event(Liquidate {
+                liquidator: caller,
+                user,
+                qouted_repaid: total_repaid,
+                debt_pools,
+                amount: to_take,
+                total_collateral: new_total_collateral,
+            });
+            self.transfer_out(caller, to_take).map_err(LAssetError::LiquidateTransferFailed)
+        }
+
+        #[ink(message)]
+        pub fn flag(&mut self, user: AccountId) -> Result<(), LAssetError> {
+            let this = self.env().account_id();
+
+            require(self.status() != AssetStatus::Removed, LAssetError::FlagRemoved)?;
+            require(self.collateral.contains(user), LAssetError::FlagForNothing)?;
+            require(!self.is_price_stale(), LAssetError::FlagStalePrice(this))?;
+
+            let result = self.health(user);
+            let mut total_mcv = result.maintenance_collateral_value;
+            let mut total_mdv = result.maintenance_debt_value;
+            let mut total_cmcv = result.category_maintenance_collateral_value;
+            let mut total_cmdv = result.category_maintenance_debt_value;
+            let mut healthy_at = result.healthy_at;
+            let mut categories = CategoryCheck::default();
+            categories.position(result.category);
+
+            let mut stale = None;
+            let mut next = self.next;
+            while next != this {
+                let result = health_next(&next, &user);
+                if result.stale_price && stale.is_none() {
+                    stale = Some(next);
+                }
+                next = result.next;
+                healthy_at = healthy_at.max(result.healthy_at);
+                total_mcv = total_mcv.saturating_add(result.maintenance_collateral_value);
+                total_mdv = total_mdv.saturating_add(result.maintenance_debt_value);
+                total_cmcv = total_cmcv.saturating_add(result.category_maintenance_collateral_value);
+                total_cmdv = total_cmdv.saturating_add(result.category_maintenance_debt_value);
+                categories.position(result.category);
+                categories.user(result.user_category);
+            }
+            if let Some(pool) = stale {
+                return Err(LAssetError::FlagStalePrice(pool));
+            }
+            let (total_mcv, total_mdv) = if categories.applies() { (total_cmcv, total_cmdv) } else { (total_mcv, total_mdv) };
+
+            let now = self.env().block_timestamp();
+            let flagged_at = if total_mdv > total_mcv {
+                let flagged_at = self.flagged.get(user).filter(|f| *f >= healthy_at).unwrap_or(now);
+                self.flagged.insert(user, &flagged_at);
+                Some(flagged_at)
+            } else {
+                self.flagged.remove(user);
+                self.healthy_at.insert(user, &now);
+                None
+            };
+
+            self.env().emit_event(Flag {user, flagged_at});
+            Ok(())
+        }
+
+        #[ink(message)]
+        pub fn write_off(&mut self, user: AccountId) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            let this = self.env().account_id();
+
+            require(self.status() != AssetStatus::Removed, LAssetError::WriteOffRemoved)?;
+            let bonds = self.bonds.get(user).ok_or(LAssetError::WriteOffWithoutBorrow)?;
+            require(!self.collateral.contains(user), LAssetError::WriteOffWithCollateral)?;
+
+            let mut next = self.next;
+            while next != this {
+                let (n, collateral) = collateral_next(&next, &user);
+                require(collateral == 0, LAssetError::WriteOffWithCollateral)?;
+                next = n;
             }
 
-            transfer(self.underlying_token, caller, to_take).map_err(LAssetError::LiquidateTransferFailed)
+            let total_borrowable = self.total_borrowable;
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(total_borrowable);
+
+            let total_debt = sub(total_liquidity, total_borrowable); //PROVED
+            let total_bonds = self.total_bonds;
+            let debt = mulw(bonds, total_debt).ceil_rate(total_bonds).unwrap_or(total_debt); //PROVED
+            let from_reserve = debt.min(total_reserve);
+
+            let new_total_bonds = sub(total_bonds, bonds); //PROVED
+            let new_total_liquidity = sub(total_liquidity, debt); //PROVED
+            let new_total_reserve = sub(total_reserve, from_reserve); //PROVED
+
+            self.total_bonds = new_total_bonds;
+            self.bonds.remove(user);
+            self.burn_isolated_bonds(user, bonds);
+
+            self.last_total_liquidity = new_total_liquidity;
+            self.total_reserve = new_total_reserve;
+            self.last_updated_at = updated_at;
+
+            self.transfer_gas(caller);
+
+            self.env().emit_event(WriteOff {
+                caller,
+                user,
+                amount: debt,
+                bonds,
+                from_reserve,
+                total_bonds: new_total_bonds,
+                total_liquidity: new_total_liquidity,
+                total_reserve: new_total_reserve,
+            });
+            Ok(())
         }
 
         fn inner_repay(&mut self, 
@@ -371,16 +901,16 @@ mod finance2 {
             user: AccountId, 
             cash: u128,
             bonds: u128,
+            max_bonds: u128,
         ) -> (u128, u128, u128, u128, u128) {
             let total_borrowable = self.total_borrowable;
-            let (total_liquidity, updated_at) = self.inner_accrue(total_borrowable);
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(total_borrowable);
             
-            let total_borrowable = self.total_borrowable;
             let total_debt = sub(total_liquidity, total_borrowable); //PROVED
             let total_bonds = self.total_bonds;
 
             let max_to_burn = mulw(cash, total_bonds).div_rate(total_debt).unwrap_or(0); //PROVED
-            let to_burn = max_to_burn.min(bonds); //PROVED
+            let to_burn = max_to_burn.min(bonds).min(max_bonds); //PROVED
             let repaid = mulw(to_burn, total_debt).ceil_rate(total_bonds).unwrap_or(0); //PROVED
             let new_cash = sub(cash, repaid); //PROVED
 
@@ -388,159 +918,393 @@ mod finance2 {
             let new_bonds = sub(bonds, to_burn); //PROVED
             let new_total_bonds = sub(total_bonds, to_burn); //PROVED
 
+            self.total_cash = sub(self.total_cash, repaid); //PROVED
             self.cash.insert(caller, &new_cash);
             
             self.total_borrowable = new_total_borrowable;
             self.last_total_liquidity = total_liquidity;
+            self.total_reserve = total_reserve;
             self.last_updated_at = updated_at;
             
             self.total_bonds = new_total_bonds;
+            self.burn_isolated_bonds(user, to_burn);
             if new_bonds != 0 {
                 self.bonds.insert(user, &new_bonds);
             } else {
                 self.bonds.remove(user);
-                self.transfer_gas(caller);
+                if !self.collateral.contains(user) {
+                    self.transfer_gas(caller);
+                }
             }
+
+            self.env().emit_event(Repay {
+                cash_owner: caller,
+                user,
+                amount: repaid,
+                bonds: to_burn,
+                total_bonds: new_total_bonds,
+                total_liquidity,
+                total_borrowable: new_total_borrowable,
+            });
             (repaid, new_total_borrowable, new_total_bonds, new_bonds, total_liquidity)
         }
 
         #[ink(message)]
         pub fn accrue(&mut self) -> Result<(), LAssetError> {
-            let (total_liquidity, updated_at) = self.inner_accrue(self.total_borrowable);
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(self.total_borrowable);
 
             self.last_total_liquidity = total_liquidity;
+            self.total_reserve = total_reserve;
             self.last_updated_at = updated_at;
 
             Ok(())
         }
 
-        #[ink(message)]
+        #[ink(message, payable)]
         pub fn repay(&mut self, user: AccountId, extra_cash: u128) -> Result<(), LAssetError> {
+            require(!self.paused.repay, LAssetError::RepayPaused)?;
             let caller = self.env().caller();
             
-            let this = self.env().account_id();
-            transfer_from(self.underlying_token, caller, this, extra_cash).map_err(LAssetError::RepayTransferFailed)?;
+            let received = self.transfer_in(caller, extra_cash, 0).map_err(LAssetError::RepayTransferFailed)?;
 
             let cash = self.cash.get(caller).unwrap_or(0);
-            let new_cash = cash.checked_add(extra_cash).ok_or(LAssetError::RepayCashOverflow)?;
+            let new_total_cash = self.total_cash.checked_add(received).ok_or(LAssetError::RepayCashOverflow)?;
+            let new_cash = add(cash, received); //PROVED
             let bonds = self.bonds.get(user).ok_or(LAssetError::RepayWithoutBorrow)?;
-            self.inner_repay(caller, user, new_cash, bonds);
+            self.total_cash = new_total_cash;
+            self.inner_repay(caller, user, new_cash, bonds, bonds);
 
             Ok(())
         }
 
-        fn inner_accrue(&self, total_borrowable: u128) -> (u128, u64) {
+        #[ink(message)]
+        pub fn account_snapshot(&self, user: AccountId) -> AccountSnapshot {
+            let price = self.price;
+            let price_scaler = self.price_scaler;
+
+            let total_borrowable = self.total_borrowable;
+            let (total_liquidity, total_reserve, _) = self.accrued(total_borrowable);
+            let total_debt = sub(total_liquidity, total_borrowable); //PROVED
+            let supplied = sub(total_liquidity, total_reserve); //PROVED
+
+            let collateral = self.collateral.get(user).unwrap_or(0);
+            let shares = self.shares.get(user).unwrap_or(0);
+            let deposit = mulw(shares, supplied).div_rate(self.total_shares).unwrap_or(0); //PROVED
+            let debt = match self.bonds.get(user) {
+                Some(b) => mulw(b, total_debt).ceil_rate(self.total_bonds).unwrap_or(total_debt), //PROVED
+                None => 0,
+            };
+            let cash = self.cash.get(user).unwrap_or(0);
+
+            AccountSnapshot {
+                collateral,
+                qouted_collateral: mulw(collateral, price).div(price_scaler).unwrap_or(u128::MAX),
+                deposit,
+                qouted_deposit: mulw(deposit, price).div(price_scaler).unwrap_or(u128::MAX),
+                debt,
+                qouted_debt: mulw(debt, self.debt_price()).ceil_up(price_scaler).unwrap_or(u128::MAX),
+                cash,
+                qouted_cash: mulw(cash, price).div(price_scaler).unwrap_or(u128::MAX),
+                spender: self.whitelist.get(user),
+            }
+        }
+
+        fn inner_accrue(&self, total_borrowable: u128) -> (u128, u128, u64) {
+            let (total_liquidity, total_reserve, updated_at) = self.accrued(total_borrowable);
+            if updated_at != self.last_updated_at {
+                let interest = sub(total_liquidity, self.last_total_liquidity); //PROVED
+                self.env().emit_event(Accrue {interest, total_liquidity, total_reserve, updated_at});
+            }
+            (total_liquidity, total_reserve, updated_at)
+        }
+
+        fn accrued(&self, total_borrowable: u128) -> (u128, u128, u64) {
             let now = self.env().block_timestamp();
             let updated_at = self.last_updated_at;
             let total_liquidity = self.last_total_liquidity;
+            let total_reserve = self.total_reserve;
             if now > updated_at {
+                let params = self.params();
                 let delta = sub(now as u128, updated_at as u128);
-                let standard_matured = self.params.standard_rate.saturating_mul(delta);
-                let emergency_matured = self.params.emergency_rate.saturating_mul(delta);
+                let standard_matured = params.standard_rate.saturating_mul(delta);
+                let emergency_matured = params.emergency_rate.saturating_mul(delta);
     
                 let debt = sub(total_liquidity, total_borrowable);
     
                 let standard_scaled = mulw(standard_matured, debt).div_rate(total_liquidity).unwrap_or(0);
                 let emergency_scaled = mulw(emergency_matured, total_borrowable).div_rate(total_liquidity).unwrap_or(0);
     
-                let standard_final = standard_scaled.saturating_add(self.params.standard_min_rate);
-                let emergency_final = self.params.emergency_max_rate.saturating_sub(emergency_scaled);
+                let standard_final = standard_scaled.saturating_add(params.standard_min_rate);
+                let emergency_final = params.emergency_max_rate.saturating_sub(emergency_scaled);
     
                 let interest_rate = standard_final.max(emergency_final);
                 let interest = mulw(debt, interest_rate).scale_up();
     
                 let new_total_liquidity = total_liquidity.saturating_add(interest);
-                (new_total_liquidity, now)    
+                let accrued = sub(new_total_liquidity, total_liquidity); //PROVED
+                let interest_reserve = mulw(accrued, params.reserve_factor).scale();
+                let new_total_reserve = add(total_reserve, interest_reserve); //PROVED
+                (new_total_liquidity, new_total_reserve, now)
             } else {
-                (total_liquidity, updated_at)
+                (total_liquidity, total_reserve, updated_at)
             }
-        }        
-    }
+        }
 
-    impl LAsset for LAssetContract {
-        #[ink(message)]
-        fn repay_or_update(&mut self, user: AccountId, cash_owner: AccountId) -> UpdateOrRepayResult {
-            let caller = self.env().caller();
+        fn inner_repay_or_update(&mut self, spender: AccountId, user: AccountId, cash_owner: AccountId) -> UpdateOrRepayResult {
+            let category = self.position_category(user);
+            let (icv, mcv, cicv, cmcv) = self.collateral.get(user).map(|c| self.collateral_values(c)).unwrap_or((0, 0, 0, 0));
 
-            let is_repay = if let Some(valid_caller) = self.whitelist.get(cash_owner) {
-                if valid_caller == caller {
-                    self.bonds.get(user)
-                } else {
-                    None
-                }
+            let is_repay = !self.paused.repay && self.whitelist.get(cash_owner) == Some(spender);
+            let (qouted_repaid, (idv, mdv, cidv, cmdv)) = match self.bonds.get(user) {
+                Some(bonds) if is_repay => {
+                    let price = self.debt_price();
+                    let price_scaler = self.price_scaler;
+
+                    let cash = self.cash.get(cash_owner).unwrap_or(0);
+
+                    self.whitelist.remove(spender);
+
+                    let max_bonds = mulw(bonds, self.params().close_factor).scale_up();
+                    let (repaid, new_borrowable, new_total_bonds, new_bonds, total_liquidity) = self.inner_repay(cash_owner, user, cash, bonds, max_bonds);
+                    let qouted_repaid = mulw(repaid, price).ceil_up(price_scaler).unwrap_or(u128::MAX);
+
+                    let total_debt = sub(total_liquidity, new_borrowable); //PROVED
+                    let debt = mulw(new_bonds, total_debt).ceil_up(new_total_bonds).unwrap_or(total_debt);
+                    let (_, mdv, _, cmdv) = self.quoted_debt_values(debt);
+
+                    let old_debt = add(debt, repaid); //PROVED
+                    let (idv, _, cidv, _) = self.quoted_debt_values(old_debt);
+
+                    (qouted_repaid, (idv, mdv, cidv, cmdv))
+                },
+                Some(_) => (0, self.accrued_debt_values(user)),
+                None => (0, (0, 0, 0, 0)),
+            };
+
+            UpdateOrRepayResult {
+                next: self.next,
+                qouted_repaid,
+                initial_collateral_value: icv,
+                initial_debt_value: idv,
+                maintenance_collateral_value: mcv,
+                maintenance_debt_value: mdv,
+                category,
+                user_category: 0,
+                category_initial_collateral_value: cicv,
+                category_initial_debt_value: cidv,
+                category_maintenance_collateral_value: cmcv,
+                category_maintenance_debt_value: cmdv,
+                stale_price: self.has_position(user) && self.is_price_stale(),
+                healthy_at: self.healthy_at.get(user).unwrap_or(0),
+            }
+        }
+
+        fn params(&self) -> AssetParams {
+            self.params.get().unwrap_or_default()
+        }
+
+        fn status(&self) -> AssetStatus {
+            self.status.get().unwrap_or_default()
+        }
+
+        fn category(&self) -> u32 {
+            self.category.get().unwrap_or(0)
+        }
+
+        fn debt_price(&self) -> u128 {
+            self.debt_price.get().unwrap_or(0)
+        }
+
+        /// Upgrades the storage written at `version`
+        fn migrate_from(&mut self, version: u32) {
+            match version {
+                1 => { // status was added
+                    self.status.set(&AssetStatus::Active);
+                },
+                2 => { // isolation was added, its params are the trailing isolated and isolated_debt_ceiling
+                    self.total_isolated_bonds.set(&0);
+                    append_fields(&self.params.key(), &(false, 0u128));
+                },
+                3 => { // categories were added
+                    self.category.set(&0);
+                    self.category_params.set(&Category::default());
+                    self.category_price.set(&0);
+                },
+                4 => { // price staleness was added
+                    self.price_updated_at.set(&0);
+                    append_fields(&self.params.key(), &0u128);
+                },
+                5 => { // debt was valued at the collateral price before, price modes were added
+                    self.debt_price.set(&self.price);
+                    append_fields(&self.params.key(), &(PriceMode::Spot, 0u128));
+                },
+                _ => {},
+            }
+        }
+
+        fn has_position(&self, user: AccountId) -> bool {
+            self.collateral.contains(user) || self.bonds.contains(user)
+        }
+
+        fn position_category(&self, user: AccountId) -> Option<u32> {
+            if self.has_position(user) {
+                Some(self.category())
             } else {
                 None
-            };
-            if let Some(bonds) = is_repay {
-                let price = self.price;
-                let price_scaler = self.price_scaler;
+            }
+        }
+
+        fn is_price_stale(&self) -> bool {
+            let max_price_age = self.params().max_price_age;
+            if max_price_age == 0 {
+                return false;
+            }
+            let age = self.env().block_timestamp().saturating_sub(self.price_updated_at.get().unwrap_or(0)) as u128;
+            age > max_price_age
+        }
 
-                let cash = self.cash.get(cash_owner).unwrap_or(0);
+        /// The category source's price when Admin sends one, otherwise `price`
+        fn category_price_or(&self, price: u128) -> u128 {
+            match self.category_price.get().unwrap_or(0) {
+                0 => price,
+                category_price => category_price,
+            }
+        }
 
-                self.whitelist.remove(caller);
+        /// Debt that does not count towards the isolated debt ceiling, so isolated collateral may not back it
+        fn has_non_isolated_debt(&self, user: AccountId, params: &AssetParams) -> bool {
+            self.bonds.get(user).is_some_and(|bonds| {
+                params.isolated_debt_ceiling == 0 || self.isolated_bonds.get(user).unwrap_or(0) < bonds
+            })
+        }
 
-                let (repaid, new_borrowable, new_total_bonds, new_bonds, total_liquidity) = self.inner_repay(cash_owner, user, cash, bonds);
-                let qouted_repaid = mulw(repaid, price).ceil_up(price_scaler).unwrap_or(u128::MAX);
-                
-                let total_debt = sub(total_liquidity, new_borrowable); //PROVED
-                let debt = mulw(new_bonds, total_debt).ceil_up(new_total_bonds).unwrap_or(total_debt);
-                let qouted_debt = mulw(debt, price).ceil_up(price_scaler).unwrap_or(u128::MAX);
-                let mdv = mulw(qouted_debt, self.params.maintenance_margin).scale_up().saturating_add(qouted_debt);
-
-                let old_debt = add(debt, repaid); //PROVED
-                let old_qouted_debt = mulw(old_debt, price).ceil_up(price_scaler).unwrap_or(u128::MAX);
-                let idv = mulw(old_qouted_debt, self.params.initial_margin).scale_up().saturating_add(old_qouted_debt);
-
-                UpdateOrRepayResult::from_repay(self.next, qouted_repaid, idv, mdv)
-            } else if let Some(c) = self.collateral.get(user) {
-                if let Some(qouted_collateral) = mulw(c, self.price).div(self.price_scaler) {
-                    let icv = mulw(qouted_collateral, self.params.initial_haircut).scale();
-                    let mcv = mulw(qouted_collateral, self.params.maintenance_haircut).scale();
-                    UpdateOrRepayResult::from_collateral(self.next, icv, mcv)
+        /// Isolated bonds are burned first, so the ceiling is released as soon as possible
+        fn burn_isolated_bonds(&mut self, user: AccountId, to_burn: u128) {
+            if let Some(isolated_bonds) = self.isolated_bonds.get(user) {
+                let burned = isolated_bonds.min(to_burn);
+                self.total_isolated_bonds.set(&sub(self.total_isolated_bonds.get().unwrap_or(0), burned)); //PROVED
+                let new_isolated_bonds = sub(isolated_bonds, burned); //PROVED
+                if new_isolated_bonds != 0 {
+                    self.isolated_bonds.insert(user, &new_isolated_bonds);
                 } else {
-                    UpdateOrRepayResult::from_collateral(self.next, u128::MAX, u128::MAX)
+                    self.isolated_bonds.remove(user);
                 }
-            } else if let Some(b) = self.bonds.get(user) {
-                let total_borrowable = self.total_borrowable;
-                let (total_liquidity, updated_at) = self.inner_accrue(total_borrowable);
+            }
+        }
+
+        /// Initial and maintenance collateral values, then the same with the category params
+        fn collateral_values(&self, collateral: u128) -> (u128, u128, u128, u128) {
+            let params = self.params();
+            let (icv, mcv) = if let Some(qouted_collateral) = mulw(collateral, self.price).div(self.price_scaler) {
+                let icv = mulw(qouted_collateral, params.initial_haircut).scale();
+                let mcv = mulw(qouted_collateral, params.maintenance_haircut).scale();
+                (icv, mcv)
+            } else {
+                (u128::MAX, u128::MAX)
+            };
+            let category = self.category_params.get().unwrap_or_default();
+            let (cicv, cmcv) = if let Some(qouted_collateral) = mulw(collateral, self.category_price_or(self.price)).div(self.price_scaler) {
+                let cicv = mulw(qouted_collateral, category.initial_haircut).scale();
+                let cmcv = mulw(qouted_collateral, category.maintenance_haircut).scale();
+                (cicv, cmcv)
+            } else {
+                (u128::MAX, u128::MAX)
+            };
+            (icv, mcv, cicv, cmcv)
+        }
+
+        /// Initial and maintenance debt values, then the same with the category params
+        fn quoted_debt_values(&self, debt: u128) -> (u128, u128, u128, u128) {
+            let qouted_debt = mulw(debt, self.debt_price()).ceil_up(self.price_scaler).unwrap_or(u128::MAX);
+            let params = self.params();
+            let idv = mulw(qouted_debt, params.initial_margin).scale_up().saturating_add(qouted_debt);
+            let mdv = mulw(qouted_debt, params.maintenance_margin).scale_up().saturating_add(qouted_debt);
+
+            let category = self.category_params.get().unwrap_or_default();
+            let qouted_debt = mulw(debt, self.category_price_or(self.debt_price())).ceil_up(self.price_scaler).unwrap_or(u128::MAX);
+            let cidv = mulw(qouted_debt, category.initial_margin).scale_up().saturating_add(qouted_debt);
+            let cmdv = mulw(qouted_debt, category.maintenance_margin).scale_up().saturating_add(qouted_debt);
+            (idv, mdv, cidv, cmdv)
+        }
+
+        fn debt_values(&self, bonds: u128, total_liquidity: u128) -> (u128, u128, u128, u128) {
+            let total_debt = sub(total_liquidity, self.total_borrowable); //PROVED
+            let debt = mulw(bonds, total_debt).ceil_rate(self.total_bonds).unwrap_or(total_debt); //PROVED
+            self.quoted_debt_values(debt)
+        }
+
+        fn accrued_debt_values(&mut self, user: AccountId) -> (u128, u128, u128, u128) {
+            if let Some(b) = self.bonds.get(user) {
+                let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(self.total_borrowable);
 
                 self.last_total_liquidity = total_liquidity;
+                self.total_reserve = total_reserve;
                 self.last_updated_at = updated_at;
 
-                let total_debt = sub(total_liquidity, total_borrowable); //PROVED
-                let debt = mulw(b, total_debt).ceil_rate(self.total_bonds).unwrap_or(total_debt); //PROVED
-                let qouted_debt = mulw(debt, self.price).ceil_up(self.price_scaler).unwrap_or(u128::MAX);
-                let idv = mulw(qouted_debt, self.params.initial_margin).scale_up().saturating_add(qouted_debt);
-                let mdv = mulw(qouted_debt, self.params.maintenance_margin).scale_up().saturating_add(qouted_debt);
-                UpdateOrRepayResult::from_debt(self.next, idv, mdv)
+                self.debt_values(b, total_liquidity)
             } else {
-                UpdateOrRepayResult::new(self.next)
+                (0, 0, 0, 0)
             }
         }
+    }
 
+    impl LAsset for LAssetContract {
         #[ink(message)]
-        fn update(&mut self, user: AccountId) -> UpdateResult {
-            if let Some(c) = self.collateral.get(user) {
-                if let Some(qouted_collateral) = mulw(c, self.price).div(self.price_scaler) {
-                    let icv = mulw(qouted_collateral, self.params.initial_haircut).scale();
-                    UpdateResult::from_collateral(self.next, icv)
-                } else {
-                    UpdateResult::from_collateral(self.next, u128::MAX)
-                }
-            } else if let Some(b) = self.bonds.get(user) {
-                let total_borrowable = self.total_borrowable;
-                let (total_liquidity, updated_at) = self.inner_accrue(total_borrowable);
+        fn repay_or_update(&mut self, user: AccountId, cash_owner: AccountId) -> UpdateOrRepayResult {
+            let caller = self.env().caller();
+            self.inner_repay_or_update(caller, user, cash_owner)
+        }
 
-                self.last_total_liquidity = total_liquidity;
-                self.last_updated_at = updated_at;
+        #[ink(message)]
+        fn collateral_of(&self, user: AccountId) -> (AccountId, u128) {
+            (self.next, self.collateral.get(user).unwrap_or(0))
+        }
 
-                let total_debt = sub(total_liquidity, total_borrowable); //PROVED
-                let debt = mulw(b, total_debt).ceil_rate(self.total_bonds).unwrap_or(total_debt); //PROVED
-                let qouted_debt = mulw(debt, self.price).ceil_up(self.price_scaler).unwrap_or(u128::MAX);
-                let idv = mulw(qouted_debt, self.params.initial_margin).scale_up().saturating_add(qouted_debt);
-                UpdateResult::from_debt(self.next, idv)
+        #[ink(message)]
+        fn health(&self, user: AccountId) -> UpdateOrRepayResult {
+            let (icv, mcv, cicv, cmcv) = self.collateral.get(user).map(|c| self.collateral_values(c)).unwrap_or((0, 0, 0, 0));
+            let (idv, mdv, cidv, cmdv) = if let Some(b) = self.bonds.get(user) {
+                let (total_liquidity, _, _) = self.accrued(self.total_borrowable);
+                self.debt_values(b, total_liquidity)
             } else {
-                UpdateResult::new(self.next)
+                (0, 0, 0, 0)
+            };
+            UpdateOrRepayResult {
+                next: self.next,
+                qouted_repaid: 0,
+                initial_collateral_value: icv,
+                initial_debt_value: idv,
+                maintenance_collateral_value: mcv,
+                maintenance_debt_value: mdv,
+                category: self.position_category(user),
+                user_category: 0,
+                category_initial_collateral_value: cicv,
+                category_initial_debt_value: cidv,
+                category_maintenance_collateral_value: cmcv,
+                category_maintenance_debt_value: cmdv,
+                stale_price: self.has_position(user) && self.is_price_stale(),
+                healthy_at: self.healthy_at.get(user).unwrap_or(0),
+            }
+        }
+
+        #[ink(message)]
+        fn update(&mut self, user: AccountId) -> UpdateResult {
+            let collateral = self.collateral.get(user);
+            let (icv, _, cicv, _) = collateral.map(|c| self.collateral_values(c)).unwrap_or((0, 0, 0, 0));
+            let (idv, _, cidv, _) = self.accrued_debt_values(user);
+            let params = self.params();
+            UpdateResult {
+                next: self.next,
+                initial_collateral_value: icv,
+                initial_debt_value: idv,
+                category: self.position_category(user),
+                user_category: 0,
+                category_initial_collateral_value: cicv,
+                category_initial_debt_value: cidv,
+                stale_price: self.has_position(user) && self.is_price_stale(),
+                has_collateral: collateral.is_some(),
+                has_isolated_collateral: collateral.is_some() && params.isolated,
+                has_non_isolated_debt: self.has_non_isolated_debt(user, &params),
             }
         }
     }
@@ -549,28 +1313,127 @@ mod finance2 {
         fn take_cash(&mut self, amount: u128, target: AccountId) -> Result<(AccountId, u128), TakeCashError> {
             let caller = self.env().caller();
             require(caller == self.admin, TakeCashError::Unauthorized)?;
+            require(!self.paused.take_cash, TakeCashError::Paused)?;
+            require(!self.native, TakeCashError::NativeNotSupported)?;
             
-            let fee = mulw(amount, self.params.take_cash_fee).scale_up();
+            let params = self.params();
+            let fee = mulw(amount, params.take_cash_fee).scale_up();
             let new_total_liquidity = self.last_total_liquidity.checked_add(fee).ok_or(TakeCashError::Overflow)?;
             let new_total_borrowable = add(self.total_borrowable, fee); //PROVED
+            let fee_reserve = mulw(fee, params.reserve_factor).scale();
+            let new_total_reserve = add(self.total_reserve, fee_reserve); //PROVED
 
             self.last_total_liquidity = new_total_liquidity;
             self.total_borrowable = new_total_borrowable;
-
+            self.total_reserve = new_total_reserve;
+
+            self.env().emit_event(TakeCash {
+                target,
+                amount,
+                fee,
+                total_liquidity: new_total_liquidity,
+                total_borrowable: new_total_borrowable,
+                total_reserve: new_total_reserve,
+            });
             let underlying_token = self.underlying_token;
             transfer(underlying_token, target, amount).map_err(TakeCashError::Transfer)?;
 
             Ok((underlying_token, fee))
         }
+
+        #[ink(message)]
+        fn withdraw_reserve(&mut self, amount: u128, target: AccountId) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::WithdrawReserveUnathorized)?;
+
+            let total_borrowable = self.total_borrowable;
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(total_borrowable);
+
+            let new_total_reserve = total_reserve.checked_sub(amount).ok_or(LAssetError::WithdrawReserveOverflow)?;
+            let new_total_borrowable = total_borrowable.checked_sub(amount).ok_or(LAssetError::WithdrawReserveTooMuch)?;
+            let new_total_liquidity = sub(total_liquidity, amount); //PROVED
+
+            self.total_reserve = new_total_reserve;
+            self.total_borrowable = new_total_borrowable;
+            self.last_total_liquidity = new_total_liquidity;
+            self.last_updated_at = updated_at;
+
+            self.env().emit_event(WithdrawReserve {
+                target,
+                amount,
+                total_liquidity: new_total_liquidity,
+                total_borrowable: new_total_borrowable,
+                total_reserve: new_total_reserve,
+            });
+            self.transfer_out(target, amount).map_err(LAssetError::WithdrawReserveTransferFailed)
+        }
+
+        #[ink(message)]
+        fn reserve(&self) -> (AccountId, u128) {
+            let (_, total_reserve, _) = self.accrued(self.total_borrowable);
+            (self.next, total_reserve)
+        }
+
+        #[ink(message)]
+        fn skim(&mut self, target: AccountId) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::SkimUnathorized)?;
+            require(!self.native, LAssetError::SkimNativeNotSupported)?;
+
+            let surplus = self.surplus().unwrap_or(0);
+
+            self.env().emit_event(Skim {target, amount: surplus});
+            transfer(self.underlying_token, target, surplus).map_err(LAssetError::SkimTransferFailed)
+        }
+
+        /// Books the difference between the pool's balance and its accounting, only for `balance_delta` underlyings
+        #[ink(message)]
+        fn sync(&mut self) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::SyncUnathorized)?;
+            require(!self.native, LAssetError::SyncNativeNotSupported)?;
+            require(self.params().balance_delta, LAssetError::SyncNotBalanceDelta)?;
+
+            let total_borrowable = self.total_borrowable;
+            let (total_liquidity, total_reserve, updated_at) = self.inner_accrue(total_borrowable);
+
+            let (new_total_liquidity, new_total_borrowable) = match self.surplus() {
+                Ok(surplus) => {
+                    let new_total_liquidity = total_liquidity.checked_add(surplus).ok_or(LAssetError::SyncOverflow)?;
+                    (new_total_liquidity, add(total_borrowable, surplus)) //PROVED
+                },
+                Err(deficit) => {
+                    let new_total_borrowable = total_borrowable.checked_sub(deficit).ok_or(LAssetError::SyncDeficitTooLarge)?;
+                    (sub(total_liquidity, deficit), new_total_borrowable) //PROVED
+                },
+            };
+            let new_total_reserve = total_reserve.min(new_total_liquidity);
+
+            self.total_borrowable = new_total_borrowable;
+            self.last_total_liquidity = new_total_liquidity;
+            self.total_reserve = new_total_reserve;
+            self.last_updated_at = updated_at;
+
+            self.env().emit_event(Sync {
+                total_liquidity: new_total_liquidity,
+                total_borrowable: new_total_borrowable,
+                total_reserve: new_total_reserve,
+            });
+            Ok(())
+        }
         
         #[ink(message)]
-        fn set_price(&mut self, price: u128, price_scaler: u128) -> Result<AccountId, LAssetError> {
+        fn set_price(&mut self, price: u128, debt_price: u128, category_price: u128, price_scaler: u128, updated_at: u64) -> Result<AccountId, LAssetError> {
             let caller = self.env().caller();
             require(caller == self.admin, LAssetError::SetPriceUnathorized)?;
             
             self.price = price;
+            self.debt_price.set(&debt_price);
+            self.category_price.set(&category_price);
             self.price_scaler = price_scaler;
-            
+            self.price_updated_at.set(&updated_at);
+
+            self.env().emit_event(SetPrice {price, debt_price, category_price, price_scaler, updated_at});
             Ok(self.next)
         }
         
@@ -579,7 +1442,93 @@ mod finance2 {
             let caller = self.env().caller();
             require(caller == self.admin, LAssetError::SetParamsUnathorized)?;
 
-            self.params = params;
+            self.env().emit_event(SetParams {params: params.clone()});
+            self.params.set(&params);
+            Ok(self.next)
+        }
+
+        #[ink(message)]
+        fn upgrade(&mut self, code_hash: Hash) -> Result<AccountId, LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::UpgradeUnathorized)?;
+
+            self.env().set_code_hash(&code_hash).map_err(|_| LAssetError::UpgradeFailed)?;
+            Ok(self.next)
+        }
+
+        #[ink(message)]
+        fn migrate(&mut self) -> Result<AccountId, LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::MigrateUnathorized)?;
+
+            let from = self.version;
+            require(from <= STORAGE_VERSION, LAssetError::MigrateFromNewerVersion)?;
+            for version in from..STORAGE_VERSION {
+                self.migrate_from(version);
+            }
+            self.version = STORAGE_VERSION;
+
+            self.env().emit_event(Migrate {from, to: STORAGE_VERSION});
+            Ok(self.next)
+        }
+
+        #[ink(message)]
+        fn set_paused(&mut self, paused: PauseFlags) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::SetPausedUnathorized)?;
+
+            self.env().emit_event(SetPaused {paused: paused.clone()});
+            self.paused = paused;
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn set_category(&mut self, category: u32, params: Category) -> Result<AccountId, LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::SetCategoryUnathorized)?;
+
+            self.env().emit_event(SetCategory {category, params: params.clone()});
+            self.category.set(&category);
+            self.category_params.set(&params);
+            Ok(self.next)
+        }
+
+        #[ink(message)]
+        fn set_status(&mut self, status: AssetStatus) -> Result<(), LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::SetStatusUnathorized)?;
+            require(self.status() != AssetStatus::Removed, LAssetError::SetStatusRemoved)?;
+            require(status != AssetStatus::Removed, LAssetError::SetStatusRemoved)?;
+
+            self.env().emit_event(SetStatus {status});
+            self.status.set(&status);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn remove(&mut self) -> Result<AccountId, LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::RemoveUnathorized)?;
+            require(self.status() == AssetStatus::ReduceOnly, LAssetError::RemoveNotReduceOnly)?;
+            require(self.total_collateral == 0 && self.total_bonds == 0, LAssetError::RemoveNotEmpty)?;
+
+            let next = self.next;
+            self.next = self.admin;
+            self.status.set(&AssetStatus::Removed);
+
+            self.env().emit_event(SetStatus {status: AssetStatus::Removed});
+            Ok(next)
+        }
+
+        #[ink(message)]
+        fn relink(&mut self, removed: AccountId, next: AccountId) -> Result<AccountId, LAssetError> {
+            let caller = self.env().caller();
+            require(caller == self.admin, LAssetError::RelinkUnathorized)?;
+
+            if self.next == removed {
+                self.next = next;
+                self.env().emit_event(Relink {removed, next});
+            }
             Ok(self.next)
         }
 
@@ -604,45 +1553,57 @@ mod finance2 {
         #[ink(message)]
         fn transfer(&mut self, to: AccountId, value: u128, _data: Vec<u8>) -> Result<(), PSP22Error> {
             let from = self.env().caller();
+            if from == to || value == 0 {
+                return Ok(());
+            }
             let from_shares = self.shares.get(from).unwrap_or(0);
             let new_from_shares = from_shares.checked_sub(value).ok_or(PSP22Error::InsufficientBalance)?;
 
-            if from != to {
-                let to_shares = self.shares.get(to).unwrap_or(0);
-                let new_to_shares = add(to_shares, value);
-                
-                self.shares.insert(from, &new_from_shares);
-                self.shares.insert(to, &new_to_shares);
-                
-                self.env().emit_event(Transfer {from: Some(from), to: Some(to), value});
-            }
+            let to_shares = self.shares.get(to).unwrap_or(0);
+            let new_to_shares = add(to_shares, value); //PROVED
+            
+            self.shares.insert(from, &new_from_shares);
+            self.shares.insert(to, &new_to_shares);
+            
+            self.env().emit_event(Transfer {from: Some(from), to: Some(to), value});
             Ok(())
         }
 
         #[ink(message)]
         fn transfer_from(&mut self, from: AccountId, to: AccountId, value: u128, _data: Vec<u8>) -> Result<(), PSP22Error> {
+            let spender = self.env().caller();
+            if from == to || value == 0 {
+                return Ok(());
+            }
+            let new_allowance = if spender != from {
+                let allowance = self.allowance.get((from, spender)).unwrap_or(0);
+                Some(allowance.checked_sub(value).ok_or(PSP22Error::InsufficientAllowance)?)
+            } else {
+                None
+            };
             let from_shares = self.shares.get(from).unwrap_or(0);
-            let allowance = self.allowance.get((from, to)).unwrap_or(0);
-            let new_allowance = allowance.checked_sub(value).ok_or(PSP22Error::InsufficientAllowance)?;
             let new_from_shares = from_shares.checked_sub(value).ok_or(PSP22Error::InsufficientBalance)?;
-            
-            if from != to {
-                let to_shares = self.shares.get(to).unwrap_or(0);    
-                let new_to_shares = add(to_shares, value); //PROVED
 
-                self.shares.insert(from, &new_from_shares);
-                self.shares.insert(to, &new_to_shares);
-                self.allowance.insert((from, to), &new_allowance);
+            let to_shares = self.shares.get(to).unwrap_or(0);
+            let new_to_shares = add(to_shares, value); //PROVED
+
+            self.shares.insert(from, &new_from_shares);
+            self.shares.insert(to, &new_to_shares);
 
-                self.env().emit_event(Approval {owner: from, spender: to, amount: new_allowance});
-                self.env().emit_event(Transfer {from: Some(from), to: Some(to), value});
+            if let Some(new_allowance) = new_allowance {
+                self.allowance.insert((from, spender), &new_allowance);
+                self.env().emit_event(Approval {owner: from, spender, amount: new_allowance});
             }
+            self.env().emit_event(Transfer {from: Some(from), to: Some(to), value});
             Ok(())
         }
 
         #[ink(message)]
         fn approve(&mut self, spender: AccountId, value: u128) -> Result<(), PSP22Error> {
             let owner = self.env().caller();
+            if owner == spender {
+                return Ok(());
+            }
             self.allowance.insert((owner, spender), &value);
             
             self.env().emit_event(Approval { owner, spender, amount: value});
@@ -652,6 +1613,9 @@ mod finance2 {
         #[ink(message)]
         fn increase_allowance(&mut self, spender: AccountId, delta_value: u128) -> Result<(), PSP22Error> {
             let owner = self.env().caller();
+            if owner == spender || delta_value == 0 {
+                return Ok(());
+            }
             let allowance = self.allowance.get((owner, spender)).unwrap_or(0);
             
             let new_allowance = allowance.saturating_add(delta_value);
@@ -665,9 +1629,12 @@ mod finance2 {
         #[ink(message)]
         fn decrease_allowance(&mut self, spender: AccountId, delta_value: u128) -> Result<(), PSP22Error> {
             let owner = self.env().caller();
+            if owner == spender || delta_value == 0 {
+                return Ok(());
+            }
             let allowance = self.allowance.get((owner, spender)).unwrap_or(0);
             
-            let new_allowance = allowance.saturating_sub(delta_value);
+            let new_allowance = allowance.checked_sub(delta_value).ok_or(PSP22Error::InsufficientAllowance)?;
 
             self.allowance.insert((owner, spender), &new_allowance);
 
@@ -736,6 +1703,10 @@ mod finance2 {
     #[cfg(any(test, fuzzing))]
     pub static mut TRANSFER_ERROR: bool = false;
     #[cfg(any(test, fuzzing))]
+    thread_local! {
+        pub static TRANSFER_FEE: core::cell::Cell<u128> = const { core::cell::Cell::new(0) };
+    }
+    #[cfg(any(test, fuzzing))]
     pub const BTC_ADDRESS: [u8; 32] = [0,1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31];
     #[cfg(any(test, fuzzing))]
     pub const ETH_ADDRESS: [u8; 32] = [1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16,17,18,19,20,21,22,23,24,25,26,27,28,29,30,31,0];
@@ -778,6 +1749,32 @@ mod finance2 {
         result
     }
 
+    #[cfg(not(any(test, fuzzing)))]
+    fn collateral_next(next: &AccountId, user: &AccountId) -> (AccountId, u128) {
+        let next: ink::contract_ref!(LAsset) = (*next).into();
+        next.collateral_of(*user)
+    }
+
+    #[cfg(any(test, fuzzing))]
+    fn collateral_next(next: &AccountId, user: &AccountId) -> (AccountId, u128) {
+        let result = get_next(next).collateral_of(*user);
+        restore_context();
+        result
+    }
+
+    #[cfg(not(any(test, fuzzing)))]
+    fn health_next(next: &AccountId, user: &AccountId) -> UpdateOrRepayResult {
+        let next: ink::contract_ref!(LAsset) = (*next).into();
+        next.health(*user)
+    }
+
+    #[cfg(any(test, fuzzing))]
+    fn health_next(next: &AccountId, user: &AccountId) -> UpdateOrRepayResult {
+        let result = get_next(next).health(*user);
+        restore_context();
+        result
+    }
+
     #[cfg(not(any(test, fuzzing)))]
     fn repay_or_update(app: AccountId, user: AccountId, cash_owner: AccountId) -> UpdateOrRepayResult {
         let mut app: ink::contract_ref!(LAsset) = app.into();
@@ -790,6 +1787,17 @@ mod finance2 {
         result
     }
 
+    #[cfg(not(any(test, fuzzing)))]
+    fn balance_of(token: AccountId, owner: AccountId) -> u128 {
+        let token: ink::contract_ref!(PSP22) = token.into();
+        token.balance_of(owner)
+    }
+    #[cfg(any(test, fuzzing))]
+    fn balance_of(token: AccountId, owner: AccountId) -> u128 {
+        let balances = unsafe { BALANCES.as_mut().unwrap() };
+        *balances.get(&(token, owner)).unwrap_or(&0)
+    }
+
     #[cfg(not(any(test, fuzzing)))]
     fn transfer_from(token: AccountId, from: AccountId, to: AccountId, value: u128) -> Result<(), PSP22Error> {
         let mut token: ink::contract_ref!(PSP22) = token.into();
@@ -800,7 +1808,8 @@ mod finance2 {
         let balances = unsafe { BALANCES.as_mut().unwrap() };
         let from_balance = balances.get(&(token, from)).unwrap_or(&0).checked_sub(value).ok_or(PSP22Error::InsufficientBalance)?;
         if from != to {
-            let to_balance = balances.get(&(token, from)).unwrap_or(&0).saturating_add(value);
+            let received = value.saturating_sub(TRANSFER_FEE.get());
+            let to_balance = balances.get(&(token, to)).unwrap_or(&0).saturating_add(received);
             balances.insert((token, from), from_balance);
             balances.insert((token, to), to_balance);
         }
@@ -0,0 +1,45 @@
+use ink::prelude::vec;
+use ink::prelude::vec::Vec;
+use ink::scale::{Decode, DecodeAll, Encode, Error, Input, Output};
+
+/// Stored value kept as raw bytes, so a migration can read it in a layout the current types no longer decode
+pub struct Encoded(pub Vec<u8>);
+
+impl Encode for Encoded {
+    fn size_hint(&self) -> usize {
+        self.0.len()
+    }
+    fn encode_to<T: Output + ?Sized>(&self, dest: &mut T) {
+        dest.write(&self.0)
+    }
+}
+
+impl Decode for Encoded {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, Error> {
+        let len = input.remaining_len()?.ok_or("Encoded requires the length of the input")?;
+        let mut raw = vec![0; len];
+        input.read(&mut raw)?;
+        Ok(Self(raw))
+    }
+}
+
+/// Extends the value stored under `key` with the fields appended to its type since it was written
+pub fn append_fields<K: Encode, F: Encode>(key: &K, fields: &F) {
+    if let Ok(Some(Encoded(mut raw))) = ink::env::get_contract_storage::<K, Encoded>(key) {
+        fields.encode_to(&mut raw);
+        ink::env::set_contract_storage(key, &Encoded(raw));
+    }
+}
+
+/// Reads the value stored under `key` in the layout of any earlier version, `appended` holds the encoded defaults
+/// of each group of fields appended to its type since, oldest first. `None` when nothing is stored or no layout fits
+pub fn get_appended<K: Encode, T: Decode>(key: &K, appended: &[Vec<u8>]) -> Option<T> {
+    let Ok(Some(Encoded(raw))) = ink::env::get_contract_storage::<K, Encoded>(key) else {
+        return None;
+    };
+    (0..=appended.len()).rev().find_map(|written| {
+        let mut value = raw.clone();
+        appended[written..].iter().for_each(|fields| value.extend_from_slice(fields));
+        T::decode_all(&mut &value[..]).ok()
+    })
+}
@@ -1,5 +1,6 @@
 use crate::errors::{LAssetError, TakeCashError};
-use ink::primitives::AccountId;
+use ink::primitives::{AccountId, Hash};
+use ink::prelude::vec::Vec;
 
 #[derive(Debug)]
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
@@ -7,28 +8,30 @@ pub struct UpdateResult {
     pub next: AccountId,
     pub initial_collateral_value: u128,
     pub initial_debt_value: u128,
+    pub has_collateral: bool,
+    pub has_isolated_collateral: bool,
+    pub has_non_isolated_debt: bool,
+    pub category: Option<u32>,
+    pub user_category: u32,
+    pub category_initial_collateral_value: u128,
+    pub category_initial_debt_value: u128,
+    pub stale_price: bool,
 }
 
 impl UpdateResult {
-    pub fn from_collateral(next: AccountId, icv: u128) -> Self {
-        Self { 
-            next,
-            initial_collateral_value: icv, 
-            initial_debt_value: 0 
-        }
-    }
-    pub fn from_debt(next: AccountId, idv: u128) -> Self {
-        Self { 
-            next,
-            initial_collateral_value: 0, 
-            initial_debt_value: idv 
-        }
-    }
     pub fn new(next: AccountId) -> Self {
         Self { 
             next,
             initial_collateral_value: 0, 
-            initial_debt_value: 0 
+            initial_debt_value: 0,
+            has_collateral: false,
+            has_isolated_collateral: false,
+            has_non_isolated_debt: false,
+            category: None,
+            user_category: 0,
+            category_initial_collateral_value: 0,
+            category_initial_debt_value: 0,
+            stale_price: false,
         }
     }
 }
@@ -42,6 +45,15 @@ pub struct UpdateOrRepayResult {
     pub initial_debt_value: u128,
     pub maintenance_collateral_value: u128,
     pub maintenance_debt_value: u128,
+    pub category: Option<u32>,
+    pub user_category: u32,
+    pub category_initial_collateral_value: u128,
+    pub category_initial_debt_value: u128,
+    pub category_maintenance_collateral_value: u128,
+    pub category_maintenance_debt_value: u128,
+    pub stale_price: bool,
+    /// Last time the pool found the user healthy, older flags do not count
+    pub healthy_at: u64,
 }
 
 impl UpdateOrRepayResult {
@@ -53,40 +65,78 @@ impl UpdateOrRepayResult {
             initial_debt_value: 0,
             maintenance_collateral_value: 0,
             maintenance_debt_value: 0,
+            category: None,
+            user_category: 0,
+            category_initial_collateral_value: 0,
+            category_initial_debt_value: 0,
+            category_maintenance_collateral_value: 0,
+            category_maintenance_debt_value: 0,
+            stale_price: false,
+            healthy_at: 0,
         }
     }
-    pub fn from_collateral(next: AccountId, icv: u128, mcv: u128) -> Self {
-        Self { 
-            next,
-            qouted_repaid: 0,
-            initial_collateral_value: icv, 
-            initial_debt_value: 0,
-            maintenance_collateral_value: mcv,
-            maintenance_debt_value: 0,
+}
+
+/// Follows the categories met along a traversal.
+/// Pools report `category` where the user holds a position and Admin reports the category the user opted into.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CategoryCheck {
+    category: Option<u32>,
+    mixed: bool,
+    user_category: u32,
+}
+
+impl CategoryCheck {
+    pub fn position(&mut self, category: Option<u32>) {
+        match (self.category, category) {
+            (_, None) => {},
+            (None, c) => self.category = c,
+            (Some(a), Some(b)) => self.mixed = self.mixed || a != b,
         }
     }
-    pub fn from_debt(next: AccountId, idv: u128, mdv: u128) -> Self {
-        Self { 
-            next,
-            qouted_repaid: 0,
-            initial_collateral_value: 0, 
-            initial_debt_value: idv,
-            maintenance_collateral_value: 0,
-            maintenance_debt_value: mdv,
-        }
+    pub fn user(&mut self, user_category: u32) {
+        self.user_category = self.user_category.max(user_category);
     }
-    pub fn from_repay(next: AccountId, qouted_repaid: u128, idv: u128, mdv: u128) -> Self {
-        Self { 
-            next,
-            qouted_repaid,
-            initial_collateral_value: 0, 
-            initial_debt_value: idv,
-            maintenance_collateral_value: 0,
-            maintenance_debt_value: mdv,
-        }
+    /// Category values apply only when the user opted in and every position belongs to that category
+    pub fn applies(&self) -> bool {
+        self.user_category != 0 && !self.mixed && self.category.is_none_or(|c| c == self.user_category)
     }
 }
 
+#[derive(Debug)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct PoolHealth {
+    pub pool: AccountId,
+    pub initial_collateral_value: u128,
+    pub initial_debt_value: u128,
+    pub maintenance_collateral_value: u128,
+    pub maintenance_debt_value: u128,
+}
+
+#[derive(Debug, Default)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct Health {
+    pub pools: Vec<PoolHealth>,
+    pub total_initial_collateral_value: u128,
+    pub total_initial_debt_value: u128,
+    pub total_maintenance_collateral_value: u128,
+    pub total_maintenance_debt_value: u128,
+}
+
+#[derive(Debug, Default, PartialEq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub struct AccountSnapshot {
+    pub collateral: u128,
+    pub qouted_collateral: u128,
+    pub deposit: u128,
+    pub qouted_deposit: u128,
+    pub debt: u128,
+    pub qouted_debt: u128,
+    pub cash: u128,
+    pub qouted_cash: u128,
+    pub spender: Option<AccountId>,
+}
+
 #[ink::trait_definition]
 pub trait LAsset {
     #[ink(message)]
@@ -94,21 +144,64 @@ pub trait LAsset {
 
     #[ink(message)]
     fn repay_or_update(&mut self, user: AccountId, cash_owner: AccountId) -> UpdateOrRepayResult;
+
+    #[ink(message)]
+    fn collateral_of(&self, user: AccountId) -> (AccountId, u128);
+
+    #[ink(message)]
+    fn health(&self, user: AccountId) -> UpdateOrRepayResult;
 }
 
 #[ink::trait_definition]
 pub trait AssetPool {
     #[ink(message)]
     fn take_cash(&mut self, amount: u128, target: AccountId) -> Result<(AccountId, u128), TakeCashError>;
+
+    #[ink(message)]
+    fn withdraw_reserve(&mut self, amount: u128, target: AccountId) -> Result<(), LAssetError>;
+
+    #[ink(message)]
+    fn reserve(&self) -> (AccountId, u128);
+
+    #[ink(message)]
+    fn skim(&mut self, target: AccountId) -> Result<(), LAssetError>;
+
+    #[ink(message)]
+    fn sync(&mut self) -> Result<(), LAssetError>;
     
+    /// Collateral is valued at `price` and debt at `debt_price`, both over `price_scaler`.
+    /// Users in the pool's category are valued at `category_price` instead, unless it is zero
     #[ink(message)]
-    fn set_price(&mut self, price: u128, price_scaler: u128) -> Result<AccountId, LAssetError>;
+    fn set_price(&mut self, price: u128, debt_price: u128, category_price: u128, price_scaler: u128, updated_at: u64) -> Result<AccountId, LAssetError>;
     
     #[ink(message)]
     fn set_params(&mut self, params: AssetParams) -> Result<AccountId, LAssetError>;
+
+    #[ink(message)]
+    fn set_paused(&mut self, paused: PauseFlags) -> Result<(), LAssetError>;
+
+    #[ink(message)]
+    fn set_category(&mut self, category: u32, params: Category) -> Result<AccountId, LAssetError>;
+
+    #[ink(message)]
+    fn set_status(&mut self, status: AssetStatus) -> Result<(), LAssetError>;
+
+    /// Detaches an emptied reduce-only pool from the chain and returns its former `next`
+    #[ink(message)]
+    fn remove(&mut self) -> Result<AccountId, LAssetError>;
+
+    /// Points `next` past `removed` if it was the successor, returns `next` afterwards
+    #[ink(message)]
+    fn relink(&mut self, removed: AccountId, next: AccountId) -> Result<AccountId, LAssetError>;
+
+    #[ink(message)]
+    fn upgrade(&mut self, code_hash: Hash) -> Result<AccountId, LAssetError>;
+
+    #[ink(message)]
+    fn migrate(&mut self) -> Result<AccountId, LAssetError>;
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 #[ink::scale_derive(Encode, Decode, TypeInfo)]
 #[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
 
@@ -124,5 +217,101 @@ pub struct AssetParams {
     pub mint_fee: u128,
     pub borrow_fee: u128,
     pub take_cash_fee: u128,
+    /// Reward at the maintenance threshold, it grows with the account's maintenance debt to collateral ratio
     pub liquidation_reward: u128,
+    pub collateral_cap: u128,
+    pub liquidity_cap: u128,
+    pub debt_cap: u128,
+    pub reserve_factor: u128,
+    pub close_factor: u128,
+    /// Cap of the growing reward, zero leaves it uncapped. Also the reward an auction ends at
+    pub max_liquidation_reward: u128,
+    pub auction_duration: u128,
+    pub balance_delta: bool,
+    /// Collateral that may only back a borrow on its own
+    pub isolated: bool,
+    /// Debt that accounts with isolated collateral may owe this pool in total, zero disallows borrowing in isolation
+    pub isolated_debt_ceiling: u128,
+    /// Longest time a price may go without an update before borrow, withdraw and liquidate refuse it, zero disables the check
+    pub max_price_age: u128,
+    /// Which price Admin sends to the pool, read by Admin only
+    pub price_mode: PriceMode,
+    pub twap_window: u128,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct PauseFlags {
+    pub deposit: bool,
+    pub withdraw: bool,
+    pub mint: bool,
+    pub burn: bool,
+    pub borrow: bool,
+    pub repay: bool,
+    pub liquidate: bool,
+    pub take_cash: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum PriceMode {
+    #[default]
+    Spot,
+    Twap,
+    /// Lower of spot and TWAP for collateral, higher for debt
+    Conservative,
+}
+
+/// Parameters shared by correlated assets, used instead of the asset's own ones for users opted into the category
+#[derive(Debug, Default, Clone)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub struct Category {
+    pub initial_margin: u128,
+    pub maintenance_margin: u128,
+    pub initial_haircut: u128,
+    pub maintenance_haircut: u128,
+    /// Asset whose price values every member, Admin converts it to the member's `price_scaler`. `None` keeps the member's own price
+    pub price_source: Option<AccountId>,
+}
+
+/// Active pools accept everything, reduce-only pools only let positions shrink
+/// (withdraw, burn, repay, liquidate) and removed pools are no longer part of the chain.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+#[cfg_attr(feature = "std", derive(ink::storage::traits::StorageLayout))]
+pub enum AssetStatus {
+    #[default]
+    Active,
+    ReduceOnly,
+    Removed,
+}
+
+impl PauseFlags {
+    pub fn union(&self, other: &Self) -> Self {
+        Self {
+            deposit: self.deposit || other.deposit,
+            withdraw: self.withdraw || other.withdraw,
+            mint: self.mint || other.mint,
+            burn: self.burn || other.burn,
+            borrow: self.borrow || other.borrow,
+            repay: self.repay || other.repay,
+            liquidate: self.liquidate || other.liquidate,
+            take_cash: self.take_cash || other.take_cash,
+        }
+    }
+    pub fn difference(&self, other: &Self) -> Self {
+        Self {
+            deposit: self.deposit && !other.deposit,
+            withdraw: self.withdraw && !other.withdraw,
+            mint: self.mint && !other.mint,
+            burn: self.burn && !other.burn,
+            borrow: self.borrow && !other.borrow,
+            repay: self.repay && !other.repay,
+            liquidate: self.liquidate && !other.liquidate,
+            take_cash: self.take_cash && !other.take_cash,
+        }
+    }
 }
@@ -1,9 +1,13 @@
 pub use ink::primitives::AccountId;
 
-pub use crate::finance2::{LAssetContract, BALANCES, BTC_ADDRESS, CALLER, CALLEE, ETH_ADDRESS, L_BTC, L_ETH, L_USDC, TRANSFER_ERROR, USDC_ADDRESS};
+pub use crate::finance2::{LAssetContract, STORAGE_VERSION, BALANCES, BTC_ADDRESS, CALLER, CALLEE, ETH_ADDRESS, L_BTC, L_ETH, L_USDC, TRANSFER_ERROR, TRANSFER_FEE, USDC_ADDRESS};
 pub use crate::errors::{LAssetError, TakeCashError};
-pub use crate::structs::{AssetParams, AssetPool, LAsset};
-pub use traits::psp22::PSP22;
+pub use crate::events::{Deposit, Flag, Liquidate};
+pub use crate::migration::Encoded;
+pub use crate::structs::{AccountSnapshot, AssetParams, AssetPool, AssetStatus, Category, CategoryCheck, LAsset, PauseFlags, PriceMode};
+pub use traits::psp22::{PSP22, PSP22Error};
+pub use ink::scale::Encode;
+pub use ink::storage::traits::StorageKey;
 
 pub fn setup_call(caller: AccountId, callee: AccountId, value: u128, timestamp: u64) {
     unsafe {
@@ -16,6 +20,16 @@ pub fn setup_call(caller: AccountId, callee: AccountId, value: u128, timestamp:
     ink::env::test::set_value_transferred::<ink::env::DefaultEnvironment>(value);
 }
 
+/// Params live behind `Lazy`, so they are read and written in the pool's own storage
+pub fn update_params(pool: &mut LAssetContract, address: AccountId, update: impl FnOnce(&mut AssetParams)) {
+    let callee = ink::env::test::callee::<ink::env::DefaultEnvironment>();
+    ink::env::test::set_callee::<ink::env::DefaultEnvironment>(address);
+    let mut params = pool.params.get().unwrap();
+    update(&mut params);
+    pool.params.set(&params);
+    ink::env::test::set_callee::<ink::env::DefaultEnvironment>(callee);
+}
+
 fn e<T: std::fmt::Debug>(m: &str, r: T) -> Result<(), String> {
     Err(format!("{}. Got: {:?}", m, r))
 }
@@ -49,10 +63,17 @@ fn default_works() {
         l_btc.price = 1;
         l_usdc.price = 1;
         l_eth.price = 1;
+        // lazy fields are stored by the callee
+        setup_call(admin, btc, 0, 0);
+        l_btc.debt_price.set(&1);
+        setup_call(admin, usdc, 0, 0);
+        l_usdc.debt_price.set(&1);
+        setup_call(admin, eth, 0, 0);
+        l_eth.debt_price.set(&1);
     }
     {
         setup_call(alice, btc, 0, timestamp);
-        match l_btc.set_price(0, 0) {
+        match l_btc.set_price(0, 0, 0, 0, 0) {
             Err(LAssetError::SetPriceUnathorized) => Ok(()),
             r => e("Set price should fail if unauthorized", r),
         }.unwrap();
@@ -72,6 +93,19 @@ fn default_works() {
             borrow_fee: 0,
             take_cash_fee: 0,
             liquidation_reward: 0,
+            collateral_cap: u128::MAX,
+            liquidity_cap: u128::MAX,
+            debt_cap: u128::MAX,
+            reserve_factor: 0,
+            close_factor: u128::MAX,
+            max_liquidation_reward: 0,
+            auction_duration: 0,
+            balance_delta: false,
+            max_price_age: 0,
+            price_mode: PriceMode::Spot,
+            twap_window: 0,
+            isolated: false,
+            isolated_debt_ceiling: 0,
         };
         match l_eth.set_params(params) {
             Err(LAssetError::SetParamsUnathorized) => Ok(()),
@@ -96,11 +130,27 @@ fn default_works() {
         setup_call(alice, btc, 1, timestamp);
         l_btc.deposit(0).unwrap();
     }
+    {
+        // a PSP22 pool only takes the gas collateral in value, anything more would be lost
+        setup_call(alice, btc, 1, timestamp);
+        match l_btc.deposit(0) {
+            Err(LAssetError::DepositTransferFailed(PSP22Error::Custom(m))) if m == "TransferredValueMismatch" => Ok(()),
+            r => e("Deposit should fail with value once the gas collateral is taken", r),
+        }.unwrap();
+        match l_btc.mint(0) {
+            Err(LAssetError::MintTransferFailed(PSP22Error::Custom(m))) if m == "TransferredValueMismatch" => Ok(()),
+            r => e("Mint should fail with value", r),
+        }.unwrap();
+        match l_btc.borrow(0) {
+            Err(LAssetError::BorrowUnexpectedValue) => Ok(()),
+            r => e("Borrow should fail with value once the gas collateral is taken", r),
+        }.unwrap();
+    }
     {
         setup_call(alice, btc, 0, timestamp);
         match l_btc.borrow(0) {
-            Err(LAssetError::BorrowWhileDepositingNotAllowed) => Ok(()),
-            r => e("Borrow while depositing should fail", r),
+            Err(LAssetError::CollateralValueTooLowAfterBorrow) => Ok(()),
+            r => e("Borrow against own empty deposit should fail", r),
         }.unwrap();
     }
     {
@@ -117,10 +167,24 @@ fn default_works() {
             r => e("Deposit should fail if caller has insufficient balance", r),
         }.unwrap();
     }
+    {
+        update_params(l_btc, btc, |p| p.collateral_cap = 0);
+        balances.insert((btc, alice), 1);
+        setup_call(alice, btc, 0, timestamp);
+        match l_btc.deposit(1) {
+            Err(LAssetError::DepositCapExceeded) => Ok(()),
+            r => e("Deposit should fail above collateral cap", r),
+        }.unwrap();
+        update_params(l_btc, btc, |p| p.collateral_cap = u128::MAX);
+    }
     {
         balances.insert((btc, alice), u128::MAX);
         setup_call(alice, btc, 0, timestamp);
         l_btc.deposit(u128::MAX).unwrap();
+
+        let event = ink::env::test::recorded_events().last().unwrap();
+        let deposit = <Deposit as ink::scale::Decode>::decode(&mut &event.data[..]).unwrap();
+        assert_eq!((deposit.user, deposit.amount, deposit.total_collateral), (alice, u128::MAX, u128::MAX));
     }
     {
         balances.insert((btc, alice), 1);
@@ -172,28 +236,72 @@ fn default_works() {
         }.unwrap();
     }
     {
-        l_btc.params.mint_fee = u128::MAX;
+        update_params(l_btc, btc, |p| p.mint_fee = u128::MAX);
         setup_call(alice, btc, 0, timestamp);
         match l_btc.mint(u128::MAX) {
             Err(LAssetError::MintFeeOverflow) => Ok(()),
             r => e("Mint should fail on fee overflow", r),
         }.unwrap();
-        l_btc.params.mint_fee = 0;
+        update_params(l_btc, btc, |p| p.mint_fee = 0);
+    }
+    {
+        update_params(l_btc, btc, |p| p.liquidity_cap = 2);
+        balances.insert((btc, alice), 3);
+        setup_call(alice, btc, 0, timestamp);
+        match l_btc.mint(3) {
+            Err(LAssetError::MintCapExceeded) => Ok(()),
+            r => e("Mint should fail above liquidity cap", r),
+        }.unwrap();
+        update_params(l_btc, btc, |p| p.liquidity_cap = u128::MAX);
     }
     {
         balances.insert((btc, alice), 3);
         setup_call(alice, btc, 0, timestamp);
         l_btc.mint(3).unwrap();
+        match l_btc.mint(0) {
+            Err(LAssetError::MintForNothing) => Ok(()),
+            r => e("Mint should fail if no shares would be minted", r),
+        }.unwrap();
     }
     {
         balances.insert((usdc, alice), 4);
         setup_call(alice, usdc, 1, timestamp);
         l_usdc.deposit(4).unwrap();
     }
+    {
+        setup_call(alice, usdc, 0, timestamp);
+        let health = l_usdc.health(alice);
+        assert_eq!((health.initial_collateral_value, health.maintenance_collateral_value), (3, 3));
+        assert_eq!((health.initial_debt_value, health.maintenance_debt_value), (0, 0));
+    }
+    {
+        update_params(l_btc, btc, |p| p.debt_cap = 0);
+        setup_call(alice, btc, 1, timestamp);
+        match l_btc.borrow(1) {
+            Err(LAssetError::BorrowCapExceeded) => Ok(()),
+            r => e("Borrow should fail above debt cap", r),
+        }.unwrap();
+        update_params(l_btc, btc, |p| p.debt_cap = u128::MAX);
+    }
     {
         setup_call(alice, btc, 1, timestamp);
         l_btc.borrow(1).unwrap();
     }
+    {
+        setup_call(alice, btc, 0, timestamp);
+        let snapshot = l_btc.account_snapshot(alice);
+        assert_eq!(snapshot, AccountSnapshot {
+            collateral: 0,
+            qouted_collateral: 0,
+            deposit: 3,
+            qouted_deposit: 3,
+            debt: 1,
+            qouted_debt: 1,
+            cash: 0,
+            qouted_cash: 0,
+            spender: None,
+        });
+    }
     {
         *transfer_error = true;
         setup_call(alice, btc, 0, timestamp);
@@ -210,13 +318,13 @@ fn default_works() {
         }.unwrap();
     }
     {
-        l_btc.params.borrow_fee = u128::MAX;
+        update_params(l_btc, btc, |p| p.borrow_fee = u128::MAX);
         setup_call(alice, btc, 0, timestamp);
         match l_btc.borrow(u128::MAX) {
             Err(LAssetError::BorrowFeeOverflow) => Ok(()),
             r => e("Borrow should fail on fee overflow", r),
         }.unwrap();
-        l_btc.params.borrow_fee = 0;
+        update_params(l_btc, btc, |p| p.borrow_fee = 0);
     }
     {
         setup_call(alice, btc, 0, timestamp);
@@ -241,11 +349,14 @@ fn default_works() {
         }.unwrap();
     }
     {
-        setup_call(alice, btc, 1, timestamp);
-        match l_btc.deposit(1) {
-            Err(LAssetError::DepositWhileBorrowingNotAllowed) => Ok(()),
-            r => e("Deposit while borrowing should fail", r),
-        }.unwrap();
+        balances.insert((btc, alice), 1);
+        setup_call(alice, btc, 0, timestamp);
+        l_btc.deposit(1).unwrap();
+        let health = l_btc.health(alice);
+        assert_eq!((health.initial_collateral_value, health.initial_debt_value), (0, 1));
+        l_btc.withdraw(1).unwrap();
+        assert!(!l_btc.collateral.contains(alice));
+        assert!(l_btc.bonds.contains(alice));
     }
     {
         balances.insert((btc, alice), u128::MAX);
@@ -343,8 +454,8 @@ fn default_works() {
         l_usdc.price_scaler = 1;
     }
     {
-        l_eth.params.maintenance_margin = u128::MAX;
-        l_btc.params.maintenance_margin = u128::MAX;
+        update_params(l_eth, eth, |p| p.maintenance_margin = u128::MAX);
+        update_params(l_btc, btc, |p| p.maintenance_margin = u128::MAX);
         setup_call(alice, usdc, 0, timestamp);
         match l_usdc.liquidate(alice) {
             Err(LAssetError::LiquidateTooMuch) => Ok(()),
@@ -359,7 +470,7 @@ fn default_works() {
         }.unwrap();
     }
     {
-        l_btc.params.take_cash_fee = u128::MAX;
+        update_params(l_btc, btc, |p| p.take_cash_fee = u128::MAX);
         setup_call(admin, btc, 0, timestamp);
         l_btc.take_cash(u128::MAX / 2, admin).unwrap();
         match l_btc.take_cash(u128::MAX / 2, admin) {
@@ -375,4 +486,759 @@ fn default_works() {
             r => e("Take cash should fail if transfer fails", r),
         }.unwrap();
     }
-}
\ No newline at end of file
+    {
+        setup_call(alice, btc, 0, timestamp);
+        match l_btc.withdraw_reserve(0, alice) {
+            Err(LAssetError::WithdrawReserveUnathorized) => Ok(()),
+            r => e("Withdraw reserve should fail if unauthorized", r),
+        }.unwrap();
+    }
+    {
+        update_params(l_btc, btc, |p| p.take_cash_fee = 1 << 127);
+        update_params(l_btc, btc, |p| p.reserve_factor = 1 << 127);
+        setup_call(admin, btc, 0, timestamp);
+        let (_, fee) = l_btc.take_cash(4, admin).unwrap();
+        assert_eq!((fee, l_btc.reserve().1), (3, 1));
+        match l_btc.withdraw_reserve(2, admin) {
+            Err(LAssetError::WithdrawReserveOverflow) => Ok(()),
+            r => e("Withdraw reserve should fail on overflow", r),
+        }.unwrap();
+        l_btc.withdraw_reserve(1, admin).unwrap();
+        assert_eq!(l_btc.reserve().1, 0);
+    }
+    {
+        setup_call(bob, eth, 0, timestamp);
+        match l_eth.write_off(bob) {
+            Err(LAssetError::WriteOffWithoutBorrow) => Ok(()),
+            r => e("Write off without borrow should fail", r),
+        }.unwrap();
+    }
+    {
+        setup_call(bob, eth, 0, timestamp);
+        match l_eth.write_off(alice) {
+            Err(LAssetError::WriteOffWithCollateral) => Ok(()),
+            r => e("Write off should fail if user has collateral", r),
+        }.unwrap();
+    }
+    {
+        let carol = AccountId::from([0xa; 32]);
+        balances.insert((eth, bob), 2);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.mint(2).unwrap();
+
+        balances.insert((usdc, carol), 3);
+        setup_call(carol, usdc, 1, timestamp);
+        l_usdc.deposit(3).unwrap();
+        setup_call(carol, eth, 1, timestamp);
+        l_eth.borrow(1).unwrap();
+
+        l_usdc.price = 0;
+        setup_call(bob, usdc, 0, timestamp);
+        l_usdc.liquidate(carol).unwrap();
+        l_usdc.price = 1;
+
+        let total_liquidity = l_eth.last_total_liquidity;
+        let total_bonds = l_eth.total_bonds;
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.write_off(carol).unwrap();
+        assert!(!l_eth.bonds.contains(carol));
+        assert_eq!(l_eth.total_bonds, total_bonds - 1);
+        assert_eq!(l_eth.last_total_liquidity, total_liquidity - 1);
+    }
+    {
+        let dave = AccountId::from([0xb; 32]);
+        balances.insert((eth, bob), 100);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.mint(100).unwrap();
+
+        balances.insert((usdc, dave), 100);
+        setup_call(dave, usdc, 1, timestamp);
+        l_usdc.deposit(100).unwrap();
+        setup_call(dave, eth, 1, timestamp);
+        l_eth.borrow(50).unwrap();
+        let bonds = l_eth.bonds.get(dave).unwrap();
+
+        balances.insert((eth, bob), 100);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.deposit_cash(usdc, 100).unwrap();
+
+        update_params(l_eth, eth, |p| p.close_factor = 1 << 127);
+        update_params(l_usdc, usdc, |p| p.liquidation_reward = 1 << 126);
+        update_params(l_usdc, usdc, |p| p.max_liquidation_reward = 1 << 127);
+        l_usdc.price_scaler = 2;
+        setup_call(bob, usdc, 0, timestamp);
+        l_usdc.liquidate(dave).unwrap();
+        l_usdc.price_scaler = 1;
+        assert_eq!(l_usdc.collateral.get(dave), Some(35));
+
+        let event = ink::env::test::recorded_events().last().unwrap();
+        let liquidate = <Liquidate as ink::scale::Decode>::decode(&mut &event.data[..]).unwrap();
+        assert_eq!((liquidate.user, liquidate.amount), (dave, 65));
+        assert_eq!(liquidate.debt_pools, vec![(eth, liquidate.qouted_repaid)]);
+
+        setup_call(bob, eth, 0, timestamp);
+        assert_eq!(l_eth.bonds.get(dave), Some(bonds - bonds / 2 - 1));
+    }
+    {
+        let erin = AccountId::from([0xc; 32]);
+        balances.insert((usdc, erin), 100);
+        setup_call(erin, usdc, 1, timestamp);
+        l_usdc.deposit(100).unwrap();
+        setup_call(erin, eth, 1, timestamp);
+        l_eth.borrow(20).unwrap();
+
+        setup_call(bob, usdc, 0, timestamp);
+        l_usdc.flag(erin).unwrap();
+        assert!(!l_usdc.flagged.contains(erin));
+
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.withdraw_cash().unwrap();
+
+        update_params(l_eth, eth, |p| p.close_factor = 1 << 126);
+        update_params(l_usdc, usdc, |p| p.auction_duration = 100);
+        l_usdc.price_scaler = 8;
+        setup_call(bob, usdc, 0, timestamp);
+        match l_usdc.liquidate(erin) {
+            Err(LAssetError::LiquidateNotFlagged) => Ok(()),
+            r => e("Liquidate in auction mode should fail if not flagged", r),
+        }.unwrap();
+
+        balances.insert((eth, bob), 100);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.deposit_cash(usdc, 100).unwrap();
+
+        setup_call(bob, usdc, 0, timestamp + 10);
+        l_usdc.flag(erin).unwrap();
+        setup_call(bob, usdc, 0, timestamp + 20);
+        l_usdc.flag(erin).unwrap();
+        assert_eq!(l_usdc.flagged.get(erin), Some(timestamp + 10));
+        let event = ink::env::test::recorded_events().last().unwrap();
+        let flag = <Flag as ink::scale::Decode>::decode(&mut &event.data[..]).unwrap();
+        assert_eq!(flag.flagged_at, Some(timestamp + 10));
+
+        setup_call(bob, usdc, 0, timestamp + 60);
+        l_usdc.liquidate(erin).unwrap();
+        l_usdc.price_scaler = 1;
+        update_params(l_usdc, usdc, |p| p.auction_duration = 0);
+        assert_eq!(l_usdc.collateral.get(erin), Some(100 - 48 - 13));
+        assert_eq!(l_usdc.flagged.get(erin), Some(timestamp + 10));
+
+        setup_call(erin, usdc, 0, timestamp + 70);
+        l_usdc.withdraw(1).unwrap();
+        assert_eq!(l_usdc.healthy_at.get(erin), Some(timestamp + 70));
+
+        l_usdc.price_scaler = 8;
+        setup_call(bob, usdc, 0, timestamp + 80);
+        l_usdc.flag(erin).unwrap();
+        l_usdc.price_scaler = 1;
+        assert_eq!(l_usdc.flagged.get(erin), Some(timestamp + 80));
+    }
+    {
+        // without max_liquidation_reward the reward is not capped
+        let gina = AccountId::from([0x1a; 32]);
+        balances.insert((eth, bob), 100);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.mint(100).unwrap();
+
+        balances.insert((usdc, gina), 100);
+        setup_call(gina, usdc, 1, timestamp);
+        l_usdc.deposit(100).unwrap();
+        setup_call(gina, eth, 1, timestamp);
+        l_eth.borrow(50).unwrap();
+
+        balances.insert((eth, bob), 100);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.deposit_cash(usdc, 100).unwrap();
+
+        update_params(l_eth, eth, |p| p.close_factor = 1 << 127);
+        update_params(l_usdc, usdc, |p| p.liquidation_reward = 1 << 127);
+        update_params(l_usdc, usdc, |p| p.max_liquidation_reward = 0);
+        l_usdc.price_scaler = 2;
+        setup_call(bob, usdc, 0, timestamp);
+        l_usdc.liquidate(gina).unwrap();
+        l_usdc.price_scaler = 1;
+        update_params(l_usdc, usdc, |p| p.liquidation_reward = 0);
+        assert_eq!(l_usdc.collateral.get(gina), Some(22));
+
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.withdraw_cash().unwrap();
+    }
+    {
+        let frank = AccountId::from([0xd; 32]);
+        balances.insert((eth, bob), 100);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.mint(100).unwrap();
+
+        balances.insert((eth, frank), 100);
+        setup_call(frank, eth, 1, timestamp);
+        l_eth.deposit(100).unwrap();
+        setup_call(frank, eth, 0, timestamp);
+        l_eth.borrow(40).unwrap();
+        let bonds = l_eth.bonds.get(frank).unwrap();
+
+        let health = l_eth.health(frank);
+        assert_eq!((health.initial_collateral_value, health.initial_debt_value), (99, 40));
+        assert_eq!((health.maintenance_collateral_value, health.maintenance_debt_value), (99, 80));
+
+        balances.insert((eth, bob), 100);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.deposit_cash(eth, 100).unwrap();
+
+        update_params(l_eth, eth, |p| p.close_factor = 1 << 127);
+        update_params(l_eth, eth, |p| p.initial_haircut = 1 << 126);
+        update_params(l_eth, eth, |p| p.maintenance_haircut = 1 << 126);
+        setup_call(bob, eth, 0, timestamp);
+        l_eth.liquidate(frank).unwrap();
+        update_params(l_eth, eth, |p| p.initial_haircut = u128::MAX);
+        update_params(l_eth, eth, |p| p.maintenance_haircut = u128::MAX);
+
+        assert_eq!(l_eth.collateral.get(frank), Some(79));
+        assert_eq!(l_eth.bonds.get(frank), Some(bonds - bonds / 2 - 1));
+    }
+    {
+        let frank = AccountId::from([0xd; 32]);
+        let grace = AccountId::from([0xe; 32]);
+        setup_call(grace, eth, 0, timestamp);
+        match l_eth.borrow_on_behalf(frank, 10) {
+            Err(LAssetError::BorrowOnBehalfNotDelegated) => Ok(()),
+            r => e("Borrow on behalf should fail without delegation", r),
+        }.unwrap();
+
+        setup_call(frank, eth, 0, timestamp);
+        l_eth.approve_delegation(grace, 10).unwrap();
+        assert_eq!(l_eth.delegation(frank, grace), 10);
+
+        setup_call(grace, eth, 0, timestamp);
+        match l_eth.borrow_on_behalf(frank, 11) {
+            Err(LAssetError::BorrowOnBehalfNotDelegated) => Ok(()),
+            r => e("Borrow on behalf should fail above delegation", r),
+        }.unwrap();
+
+        let bonds = l_eth.bonds.get(frank).unwrap();
+        l_eth.borrow_on_behalf(frank, 10).unwrap();
+        assert_eq!(l_eth.delegation(frank, grace), 0);
+        assert_eq!(l_eth.bonds.get(frank), Some(bonds + 10));
+        assert!(!l_eth.bonds.contains(grace));
+        assert_eq!(balances.get(&(eth, grace)), Some(&10));
+    }
+    {
+        let grace = AccountId::from([0xe; 32]);
+        let router = AccountId::from([0xf; 32]);
+        balances.insert((eth, router), 20);
+        setup_call(router, eth, 1, timestamp);
+        match l_eth.deposit_for(grace, 10) {
+            Err(LAssetError::DepositForNotApproved) => Ok(()),
+            r => e("Deposit for user should fail without approval", r),
+        }.unwrap();
+
+        setup_call(grace, eth, 0, timestamp);
+        l_eth.approve_depositor(router, true).unwrap();
+        setup_call(router, eth, 0, timestamp);
+        match l_eth.deposit_for(grace, 10) {
+            Err(LAssetError::FirstDepositRequiresGasCollateral) => Ok(()),
+            r => e("First deposit for user should fail without gas collateral", r),
+        }.unwrap();
+
+        balances.insert((eth, router), 20);
+        setup_call(router, eth, 1, timestamp);
+        l_eth.deposit_for(grace, 10).unwrap();
+        assert_eq!(l_eth.collateral.get(grace), Some(10));
+        assert!(!l_eth.collateral.contains(router));
+
+        setup_call(grace, eth, 0, timestamp);
+        l_eth.approve_depositor(router, false).unwrap();
+        assert!(!l_eth.depositors.contains((grace, router)));
+
+        setup_call(router, eth, 0, timestamp);
+        l_eth.mint_to(grace, 10).unwrap();
+        let shares = l_eth.shares.get(grace).unwrap();
+        assert!(!l_eth.shares.contains(router));
+        assert_eq!(balances.get(&(eth, router)), Some(&0));
+
+        setup_call(grace, eth, 0, timestamp);
+        l_eth.burn_to(router, shares).unwrap();
+        assert_eq!(l_eth.shares.get(grace), Some(0));
+        let burned = *balances.get(&(eth, router)).unwrap();
+        assert!(burned > 0);
+
+        l_eth.withdraw_to(router, 10).unwrap();
+        assert!(!l_eth.collateral.contains(grace));
+        assert_eq!(balances.get(&(eth, router)), Some(&(burned + 10)));
+        assert_eq!(balances.get(&(eth, grace)), Some(&10));
+    }
+    {
+        update_params(l_usdc, usdc, |p| p.balance_delta = true);
+        balances.insert((usdc, usdc), 0);
+        balances.insert((usdc, bob), 10);
+        TRANSFER_FEE.set(1);
+        setup_call(bob, usdc, 0, timestamp);
+        let total_cash = l_usdc.total_cash;
+        l_usdc.deposit_cash(bob, 10).unwrap();
+        TRANSFER_FEE.set(0);
+        assert_eq!(l_usdc.cash.get(bob), Some(9));
+        assert_eq!(l_usdc.total_cash, total_cash + 9);
+        l_usdc.withdraw_cash().unwrap();
+        assert_eq!(l_usdc.total_cash, total_cash);
+        update_params(l_usdc, usdc, |p| p.balance_delta = false);
+    }
+    {
+        let accounted = l_usdc.total_collateral + l_usdc.total_borrowable + l_usdc.total_cash;
+        let total_liquidity = l_usdc.last_total_liquidity;
+        let total_borrowable = l_usdc.total_borrowable;
+        balances.insert((usdc, usdc), accounted + 5);
+        setup_call(bob, usdc, 0, timestamp);
+        match l_usdc.sync() {
+            Err(LAssetError::SyncUnathorized) => Ok(()),
+            r => e("Sync should fail if unauthorized", r),
+        }.unwrap();
+        setup_call(admin, usdc, 0, timestamp);
+        match l_usdc.sync() {
+            Err(LAssetError::SyncNotBalanceDelta) => Ok(()),
+            r => e("Sync should fail without balance delta accounting", r),
+        }.unwrap();
+
+        update_params(l_usdc, usdc, |p| p.balance_delta = true);
+        l_usdc.sync().unwrap();
+        assert_eq!(l_usdc.last_total_liquidity, total_liquidity + 5);
+        assert_eq!(l_usdc.total_borrowable, total_borrowable + 5);
+
+        balances.insert((usdc, usdc), accounted + 3);
+        l_usdc.sync().unwrap();
+        assert_eq!(l_usdc.last_total_liquidity, total_liquidity + 3);
+        assert_eq!(l_usdc.total_borrowable, total_borrowable + 3);
+
+        update_params(l_usdc, usdc, |p| p.balance_delta = false);
+
+        balances.insert((usdc, usdc), accounted + 5);
+        setup_call(bob, usdc, 0, timestamp);
+        match l_usdc.skim(bob) {
+            Err(LAssetError::SkimUnathorized) => Ok(()),
+            r => e("Skim should fail if unauthorized", r),
+        }.unwrap();
+        balances.insert((usdc, admin), 0);
+        setup_call(admin, usdc, 0, timestamp);
+        l_usdc.skim(admin).unwrap();
+        assert_eq!(balances.get(&(usdc, admin)), Some(&2));
+    }
+    {
+        let henry = AccountId::from([0x10; 32]);
+        update_params(l_eth, eth, |p| p.isolated = true);
+        balances.insert((eth, henry), 10);
+        setup_call(henry, eth, 1, timestamp);
+        l_eth.deposit(10).unwrap();
+
+        setup_call(henry, eth, 0, timestamp);
+        match l_eth.borrow(1) {
+            Err(LAssetError::BorrowNotAllowedInIsolation) => Ok(()),
+            r => e("Borrow should fail if asset is not allowed in isolation", r),
+        }.unwrap();
+
+        update_params(l_eth, eth, |p| p.isolated_debt_ceiling = 2);
+        match l_eth.borrow(3) {
+            Err(LAssetError::BorrowIsolatedCeilingExceeded) => Ok(()),
+            r => e("Borrow should fail above isolated debt ceiling", r),
+        }.unwrap();
+        l_eth.borrow(1).unwrap();
+        let bonds = l_eth.bonds.get(henry).unwrap();
+        assert_eq!(l_eth.isolated_bonds.get(henry), Some(bonds));
+        assert_eq!(l_eth.total_isolated_bonds.get(), Some(bonds));
+
+        balances.insert((usdc, henry), 1);
+        setup_call(henry, usdc, 1, timestamp);
+        l_usdc.deposit(1).unwrap();
+        setup_call(henry, eth, 0, timestamp);
+        match l_eth.borrow(1) {
+            Err(LAssetError::BorrowIsolatedWithOtherCollateral) => Ok(()),
+            r => e("Borrow should fail with isolated and other collateral", r),
+        }.unwrap();
+        setup_call(henry, usdc, 0, timestamp);
+        l_usdc.withdraw(1).unwrap();
+
+        let ivan = AccountId::from([0x11; 32]);
+        balances.insert((usdc, ivan), 10);
+        setup_call(ivan, usdc, 1, timestamp);
+        l_usdc.deposit(10).unwrap();
+        setup_call(ivan, eth, 1, timestamp);
+        l_eth.borrow(1).unwrap();
+        assert_eq!(l_eth.isolated_bonds.get(ivan), None);
+        balances.insert((eth, ivan), 10);
+        setup_call(ivan, eth, 0, timestamp);
+        l_eth.deposit(10).unwrap();
+
+        setup_call(ivan, usdc, 0, timestamp);
+        match l_usdc.withdraw(5) {
+            Err(LAssetError::WithdrawIsolatedWithOtherCollateral) => Ok(()),
+            r => e("Withdraw should fail if isolated collateral is left with other collateral", r),
+        }.unwrap();
+        match l_usdc.withdraw(10) {
+            Err(LAssetError::WithdrawNotAllowedInIsolation) => Ok(()),
+            r => e("Withdraw should fail if only isolated collateral is left for non-isolated debt", r),
+        }.unwrap();
+        setup_call(ivan, eth, 0, timestamp);
+        l_eth.withdraw(10).unwrap();
+
+        update_params(l_eth, eth, |p| p.isolated = false);
+        update_params(l_eth, eth, |p| p.isolated_debt_ceiling = 0);
+    }
+    {
+        let henry = AccountId::from([0x10; 32]);
+        setup_call(admin, eth, 0, timestamp);
+        l_eth.set_price(1, 1, 0, 1, timestamp).unwrap();
+        update_params(l_eth, eth, |p| p.max_price_age = 10);
+
+        setup_call(henry, eth, 0, timestamp + 11);
+        match l_eth.withdraw(0) {
+            Err(LAssetError::WithdrawStalePrice(pool)) if pool == eth => Ok(()),
+            r => e("Withdraw should fail if price is stale", r),
+        }.unwrap();
+        match l_eth.liquidate(henry) {
+            Err(LAssetError::LiquidateStalePrice(pool)) if pool == eth => Ok(()),
+            r => e("Liquidate should fail if price is stale", r),
+        }.unwrap();
+        match l_eth.flag(henry) {
+            Err(LAssetError::FlagStalePrice(pool)) if pool == eth => Ok(()),
+            r => e("Flag should fail if price is stale", r),
+        }.unwrap();
+        setup_call(henry, usdc, 1, timestamp + 11);
+        match l_usdc.borrow(0) {
+            Err(LAssetError::BorrowStalePrice(pool)) if pool == eth => Ok(()),
+            r => e("Borrow should fail if price of a held asset is stale", r),
+        }.unwrap();
+
+        setup_call(henry, eth, 0, timestamp + 10);
+        l_eth.withdraw(0).unwrap();
+        update_params(l_eth, eth, |p| p.max_price_age = 0);
+    }
+    {
+        let henry = AccountId::from([0x10; 32]);
+        let category = Category {
+            initial_margin: 0,
+            maintenance_margin: 0,
+            initial_haircut: u128::MAX,
+            maintenance_haircut: u128::MAX,
+            price_source: None,
+        };
+        setup_call(alice, eth, 0, timestamp);
+        match l_eth.set_category(1, category.clone()) {
+            Err(LAssetError::SetCategoryUnathorized) => Ok(()),
+            r => e("Set category should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(admin, eth, 0, timestamp);
+        assert_eq!(l_eth.set_category(1, category).unwrap(), btc);
+        l_eth.set_price(1, 1, 2, 1, timestamp).unwrap();
+        let result = l_eth.health(henry);
+        assert_eq!(result.category, Some(1));
+        assert_eq!(result.user_category, 0);
+        assert!(result.category_initial_collateral_value > result.initial_collateral_value);
+        assert!(result.category_initial_debt_value > result.initial_debt_value);
+        assert_eq!(l_eth.health(bob).category, None);
+        l_eth.set_category(0, Category::default()).unwrap();
+        l_eth.set_price(1, 1, 0, 1, timestamp).unwrap();
+
+        let mut categories = CategoryCheck::default();
+        categories.position(Some(1));
+        categories.position(None);
+        assert!(!categories.applies());
+        categories.user(1);
+        assert!(categories.applies());
+        categories.position(Some(2));
+        assert!(!categories.applies());
+    }
+    {
+        let paused = PauseFlags {
+            deposit: true,
+            withdraw: true,
+            mint: true,
+            burn: true,
+            borrow: true,
+            repay: true,
+            liquidate: true,
+            take_cash: true,
+        };
+        setup_call(alice, eth, 0, timestamp);
+        match l_eth.set_paused(paused.clone()) {
+            Err(LAssetError::SetPausedUnathorized) => Ok(()),
+            r => e("Set paused should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(admin, eth, 0, timestamp);
+        l_eth.set_paused(paused).unwrap();
+        setup_call(alice, eth, 0, timestamp);
+        match l_eth.deposit(0) {
+            Err(LAssetError::DepositPaused) => Ok(()),
+            r => e("Deposit should fail if paused", r),
+        }.unwrap();
+        match l_eth.withdraw(0) {
+            Err(LAssetError::WithdrawPaused) => Ok(()),
+            r => e("Withdraw should fail if paused", r),
+        }.unwrap();
+        match l_eth.mint(0) {
+            Err(LAssetError::MintPaused) => Ok(()),
+            r => e("Mint should fail if paused", r),
+        }.unwrap();
+        match l_eth.burn(0) {
+            Err(LAssetError::BurnPaused) => Ok(()),
+            r => e("Burn should fail if paused", r),
+        }.unwrap();
+        match l_eth.borrow(0) {
+            Err(LAssetError::BorrowPaused) => Ok(()),
+            r => e("Borrow should fail if paused", r),
+        }.unwrap();
+        match l_eth.repay(alice, 0) {
+            Err(LAssetError::RepayPaused) => Ok(()),
+            r => e("Repay should fail if paused", r),
+        }.unwrap();
+        match l_eth.liquidate(alice) {
+            Err(LAssetError::LiquidatePaused) => Ok(()),
+            r => e("Liquidate should fail if paused", r),
+        }.unwrap();
+        setup_call(admin, eth, 0, timestamp);
+        match l_eth.take_cash(0, admin) {
+            Err(TakeCashError::Paused) => Ok(()),
+            r => e("Take cash should fail if paused", r),
+        }.unwrap();
+        l_eth.set_paused(PauseFlags::default()).unwrap();
+    }
+    {
+        setup_call(alice, eth, 0, timestamp);
+        match l_eth.upgrade([0; 32].into()) {
+            Err(LAssetError::UpgradeUnathorized) => Ok(()),
+            r => e("Upgrade should fail if unauthorized", r),
+        }.unwrap();
+        match l_eth.migrate() {
+            Err(LAssetError::MigrateUnathorized) => Ok(()),
+            r => e("Migrate should fail if unauthorized", r),
+        }.unwrap();
+
+        l_eth.version = 0;
+        setup_call(admin, eth, 0, timestamp);
+        assert_eq!(l_eth.migrate().unwrap(), btc);
+        assert_eq!(l_eth.version, STORAGE_VERSION);
+    }
+    {
+        setup_call(alice, eth, 0, timestamp);
+        match l_eth.set_status(AssetStatus::ReduceOnly) {
+            Err(LAssetError::SetStatusUnathorized) => Ok(()),
+            r => e("Set status should fail if unauthorized", r),
+        }.unwrap();
+        match l_eth.remove() {
+            Err(LAssetError::RemoveUnathorized) => Ok(()),
+            r => e("Remove should fail if unauthorized", r),
+        }.unwrap();
+        setup_call(alice, usdc, 0, timestamp);
+        match l_usdc.relink(eth, btc) {
+            Err(LAssetError::RelinkUnathorized) => Ok(()),
+            r => e("Relink should fail if unauthorized", r),
+        }.unwrap();
+
+        setup_call(admin, eth, 0, timestamp);
+        match l_eth.remove() {
+            Err(LAssetError::RemoveNotReduceOnly) => Ok(()),
+            r => e("Remove should fail if pool is active", r),
+        }.unwrap();
+        match l_eth.set_status(AssetStatus::Removed) {
+            Err(LAssetError::SetStatusRemoved) => Ok(()),
+            r => e("Set status should not remove the pool", r),
+        }.unwrap();
+        l_eth.set_status(AssetStatus::ReduceOnly).unwrap();
+
+        setup_call(alice, eth, 0, timestamp);
+        match l_eth.deposit(0) {
+            Err(LAssetError::DepositReduceOnly) => Ok(()),
+            r => e("Deposit should fail if reduce-only", r),
+        }.unwrap();
+        match l_eth.mint(0) {
+            Err(LAssetError::MintReduceOnly) => Ok(()),
+            r => e("Mint should fail if reduce-only", r),
+        }.unwrap();
+        match l_eth.borrow(0) {
+            Err(LAssetError::BorrowReduceOnly) => Ok(()),
+            r => e("Borrow should fail if reduce-only", r),
+        }.unwrap();
+
+        let (total_collateral, total_bonds) = (l_eth.total_collateral, l_eth.total_bonds);
+        l_eth.total_collateral = 1;
+        setup_call(admin, eth, 0, timestamp);
+        match l_eth.remove() {
+            Err(LAssetError::RemoveNotEmpty) => Ok(()),
+            r => e("Remove should fail while collateral is left", r),
+        }.unwrap();
+
+        l_eth.total_collateral = 0;
+        l_eth.total_bonds = 0;
+        assert_eq!(l_eth.remove().unwrap(), btc);
+        assert_eq!(l_eth.next, admin);
+        assert_eq!(l_eth.status.get(), Some(AssetStatus::Removed));
+        match l_eth.set_status(AssetStatus::Active) {
+            Err(LAssetError::SetStatusRemoved) => Ok(()),
+            r => e("Removed pool should not be reactivated", r),
+        }.unwrap();
+
+        setup_call(alice, eth, 0, timestamp);
+        match l_eth.withdraw(0) {
+            Err(LAssetError::WithdrawRemoved) => Ok(()),
+            r => e("Withdraw should fail if removed", r),
+        }.unwrap();
+        match l_eth.borrow(0) {
+            Err(LAssetError::BorrowRemoved) => Ok(()),
+            r => e("Borrow should fail if removed", r),
+        }.unwrap();
+        match l_eth.liquidate(alice) {
+            Err(LAssetError::LiquidateRemoved) => Ok(()),
+            r => e("Liquidate should fail if removed", r),
+        }.unwrap();
+        match l_eth.flag(alice) {
+            Err(LAssetError::FlagRemoved) => Ok(()),
+            r => e("Flag should fail if removed", r),
+        }.unwrap();
+        match l_eth.write_off(alice) {
+            Err(LAssetError::WriteOffRemoved) => Ok(()),
+            r => e("Write off should fail if removed", r),
+        }.unwrap();
+
+        setup_call(admin, btc, 0, timestamp);
+        assert_eq!(l_btc.relink(eth, btc).unwrap(), usdc);
+        setup_call(admin, usdc, 0, timestamp);
+        assert_eq!(l_usdc.relink(eth, btc).unwrap(), btc);
+        assert_eq!(l_usdc.next, btc);
+
+        l_eth.total_collateral = total_collateral;
+        l_eth.total_bonds = total_bonds;
+    }
+}
+
+#[ink::test]
+fn psp22_works() {
+    let btc = AccountId::from(BTC_ADDRESS);
+    let usdc = AccountId::from(USDC_ADDRESS);
+    let admin = AccountId::from([0x4; 32]);
+
+    let alice = AccountId::from([0x8; 32]);
+    let bob = AccountId::from([0x9; 32]);
+    let charlie = AccountId::from([0xa; 32]);
+
+    ink::env::test::set_caller::<ink::env::DefaultEnvironment>(admin);
+    ink::env::test::set_callee::<ink::env::DefaultEnvironment>(btc);
+    let mut l_btc = LAssetContract::new(btc, usdc, 1);
+    l_btc.total_shares = 10;
+    l_btc.shares.insert(alice, &10);
+
+    traits::psp22_tests::run(&mut l_btc, alice, bob, charlie);
+}
+
+#[ink::test]
+fn native_works() {
+    let native = AccountId::from([0x5; 32]);
+    let admin = AccountId::from([0x4; 32]);
+
+    let alice = AccountId::from([0x8; 32]);
+    let bob = AccountId::from([0x9; 32]);
+
+    setup_call(admin, native, 0, 0);
+    let mut l_native = LAssetContract::new_native(native, 1, 12);
+    assert!(l_native.native);
+    assert_eq!((l_native.underlying_token, l_native.decimals), (native, 12));
+    l_native.price = 1;
+    l_native.debt_price.set(&1);
+    {
+        setup_call(alice, native, 10, 0);
+        match l_native.deposit(10) {
+            Err(LAssetError::FirstDepositRequiresGasCollateral) => Ok(()),
+            r => e("First native deposit should fail without gas collateral", r),
+        }.unwrap();
+    }
+    {
+        setup_call(alice, native, 11, 0);
+        l_native.deposit(10).unwrap();
+        assert_eq!(l_native.collateral.get(alice), Some(10));
+    }
+    {
+        setup_call(alice, native, 6, 0);
+        match l_native.deposit(5) {
+            Err(LAssetError::DepositTransferFailed(_)) => Ok(()),
+            r => e("Native deposit should fail if transferred value does not match", r),
+        }.unwrap();
+    }
+    {
+        setup_call(bob, native, 20, 0);
+        l_native.mint(20).unwrap();
+        assert_eq!(l_native.balance_of(bob), 20);
+    }
+    {
+        setup_call(admin, native, 0, 0);
+        match l_native.take_cash(1, admin) {
+            Err(TakeCashError::NativeNotSupported) => Ok(()),
+            r => e("Take cash should fail for native pool", r),
+        }.unwrap();
+    }
+    {
+        ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(native, 1_000_031);
+        ink::env::test::set_account_balance::<ink::env::DefaultEnvironment>(alice, 1_000_000);
+        setup_call(alice, native, 0, 0);
+        l_native.withdraw(10).unwrap();
+        assert!(!l_native.collateral.contains(alice));
+        assert_eq!(ink::env::test::get_account_balance::<ink::env::DefaultEnvironment>(alice), Ok(1_000_011));
+    }
+}
+
+#[ink::test]
+fn migrate_works() {
+    let btc = AccountId::from(BTC_ADDRESS);
+    let usdc = AccountId::from(USDC_ADDRESS);
+    let admin = AccountId::from([0x4; 32]);
+    let alice = AccountId::from([0x8; 32]);
+    let root_key = LAssetContract::KEY;
+
+    // storage written by version 1, mappings and lazy fields are kept under their own keys
+    setup_call(admin, btc, 0, 0);
+    let root = (
+        (1u32, admin, btc, false, 0u64, usdc, 7u128),
+        (0u128, 0u128, 0u128, 0u128, 0u128),
+        (PauseFlags::default(), 3u128, 1u128, 0u128),
+        (Some("L-TestToken".to_string()), Some("L-TT".to_string()), 16u8, 1u128),
+    );
+    ink::env::set_contract_storage(&root_key, &Encoded(root.encode()));
+    let mut l_btc: LAssetContract = ink::env::get_contract_storage(&root_key).unwrap().unwrap();
+    // params written by version 1 end before the fields appended since
+    let mut params = AssetParams {
+        collateral_cap: 5,
+        ..Default::default()
+    }.encode();
+    params.truncate(params.len() - (false, 0u128, 0u128, PriceMode::Spot, 0u128).encode().len());
+    ink::env::set_contract_storage(&l_btc.params.key(), &Encoded(params));
+    {
+        setup_call(alice, btc, 0, 0);
+        match l_btc.migrate() {
+            Err(LAssetError::MigrateUnathorized) => Ok(()),
+            r => e("Migrate should fail if unauthorized", r),
+        }.unwrap();
+    }
+    {
+        setup_call(admin, btc, 0, 0);
+        assert_eq!(l_btc.migrate().unwrap(), usdc);
+        assert_eq!(l_btc.version, STORAGE_VERSION);
+        assert_eq!(l_btc.total_collateral, 7);
+        assert_eq!(l_btc.gas_collateral, 1);
+        assert_eq!(l_btc.status.get(), Some(AssetStatus::Active));
+        assert_eq!(l_btc.total_isolated_bonds.get(), Some(0));
+        assert_eq!(l_btc.category.get(), Some(0));
+        assert_eq!(l_btc.category_price.get(), Some(0));
+        let params = l_btc.params.get().unwrap();
+        assert_eq!(params.collateral_cap, 5);
+        assert!(!params.isolated);
+        assert_eq!(params.isolated_debt_ceiling, 0);
+        assert_eq!(params.max_price_age, 0);
+        assert_eq!(l_btc.price_updated_at.get(), Some(0));
+        assert_eq!(l_btc.debt_price.get(), Some(3));
+        assert_eq!(params.price_mode, PriceMode::Spot);
+        assert_eq!(params.twap_window, 0);
+    }
+    {
+        l_btc.version = STORAGE_VERSION + 1;
+        match l_btc.migrate() {
+            Err(LAssetError::MigrateFromNewerVersion) => Ok(()),
+            r => e("Migrate should fail from a newer version", r),
+        }.unwrap();
+    }
+}
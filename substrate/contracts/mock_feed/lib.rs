@@ -0,0 +1,50 @@
+#![cfg_attr(not(feature = "std"), no_std, no_main)]
+
+/// PriceFeed returning whatever was last set, meant for tests only
+#[ink::contract]
+mod mock_feed {
+    use ink::storage::Mapping;
+    use traits::price_feed::{PriceFeed, PriceFeedError};
+
+    type Observation = (u128, u128, Timestamp);
+
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct MockFeed {
+        pub prices: Mapping<AccountId, Observation>,
+    }
+
+    impl MockFeed {
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        #[ink(message)]
+        pub fn set_price(&mut self, asset: AccountId, price: u128, price_scaler: u128, updated_at: Timestamp) {
+            self.prices.insert(asset, &(price, price_scaler, updated_at));
+        }
+    }
+
+    impl PriceFeed for MockFeed {
+        #[ink(message)]
+        fn latest_price(&self, asset: AccountId) -> Result<Observation, PriceFeedError> {
+            self.prices.get(asset).ok_or(PriceFeedError::UnknownAsset)
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[ink::test]
+        fn set_price_works() {
+            let asset = AccountId::from([0x1; 32]);
+            let mut feed = MockFeed::new();
+            assert!(matches!(feed.latest_price(asset), Err(PriceFeedError::UnknownAsset)));
+
+            feed.set_price(asset, 2, 3, 4);
+            assert_eq!(feed.latest_price(asset).unwrap(), (2, 3, 4));
+        }
+    }
+}
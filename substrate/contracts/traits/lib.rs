@@ -1,5 +1,8 @@
 #![cfg_attr(not(feature = "std"), no_std, no_main)]
 
 pub mod psp22;
+pub mod price_feed;
+#[cfg(feature = "psp22-tests")]
+pub mod psp22_tests;
 
 
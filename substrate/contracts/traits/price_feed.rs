@@ -0,0 +1,21 @@
+use ink::primitives::AccountId;
+use ink::prelude::string::String;
+
+#[derive(Debug)]
+#[ink::scale_derive(Encode, Decode, TypeInfo)]
+pub enum PriceFeedError {
+    /// Custom error type for adapter-specific errors.
+    Custom(String),
+    /// Returned when the feed has no price for the asset.
+    UnknownAsset,
+}
+
+#[ink::trait_definition]
+pub trait PriceFeed {
+    /// Returns the latest `(price, price_scaler, updated_at)` of `asset`.
+    ///
+    /// `price / price_scaler` is the value of the smallest unit of `asset` and `updated_at`
+    /// is the timestamp of the observation in milliseconds.
+    #[ink(message)]
+    fn latest_price(&self, asset: AccountId) -> Result<(u128, u128, u64), PriceFeedError>;
+}
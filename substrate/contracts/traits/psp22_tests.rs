@@ -0,0 +1,254 @@
+use ink::env::test::{recorded_events, set_caller, EmittedEvent};
+use ink::env::{DefaultEnvironment, Event};
+use ink::prelude::vec::Vec;
+use ink::primitives::AccountId;
+use ink::scale::Decode;
+use crate::psp22::{Approval, PSP22, PSP22Error, Transfer};
+
+/// PSP22 implementation with the message outputs resolved to their concrete types.
+pub trait Token: PSP22<
+    totalSupplyOutput = u128,
+    balanceOfOutput = u128,
+    allowanceOutput = u128,
+    transferOutput = Result<(), PSP22Error>,
+    transferFromOutput = Result<(), PSP22Error>,
+    approveOutput = Result<(), PSP22Error>,
+    increaseAllowanceOutput = Result<(), PSP22Error>,
+    decreaseAllowanceOutput = Result<(), PSP22Error>,
+> {}
+
+impl<T> Token for T where T: PSP22<
+    totalSupplyOutput = u128,
+    balanceOfOutput = u128,
+    allowanceOutput = u128,
+    transferOutput = Result<(), PSP22Error>,
+    transferFromOutput = Result<(), PSP22Error>,
+    approveOutput = Result<(), PSP22Error>,
+    increaseAllowanceOutput = Result<(), PSP22Error>,
+    decreaseAllowanceOutput = Result<(), PSP22Error>,
+> {}
+
+/// Runs the PSP22 conformance checks against `token`.
+/// `owner` must hold at least 2 tokens, `owner`, `spender` and `recipient` must be distinct
+/// and no allowances between them may be set.
+pub fn run<T: Token>(token: &mut T, owner: AccountId, spender: AccountId, recipient: AccountId) {
+    let total_supply = token.total_supply();
+
+    transfer_works(token, owner, recipient);
+    transfer_noop_works(token, owner, recipient);
+    transfer_fails_on_insufficient_balance(token, owner, recipient);
+    approve_works(token, owner, spender);
+    transfer_from_spends_caller_allowance(token, owner, spender, recipient);
+    transfer_from_fails_on_insufficient_allowance(token, owner, spender, recipient);
+    transfer_from_fails_on_insufficient_balance(token, owner, spender, recipient);
+    transfer_from_by_owner_needs_no_allowance(token, owner, recipient);
+    transfer_from_noop_works(token, owner, spender, recipient);
+    increase_allowance_works(token, owner, spender);
+    decrease_allowance_works(token, owner, spender);
+
+    assert_eq!(token.total_supply(), total_supply, "total supply should not change");
+}
+
+fn events_after(count: usize) -> Vec<EmittedEvent> {
+    recorded_events().skip(count).collect()
+}
+
+fn decode_transfer(event: &EmittedEvent) -> Transfer {
+    assert_eq!(event.topics.first().map(|t| t.as_slice()), Transfer::SIGNATURE_TOPIC.as_ref().map(|t| t.as_slice()), "expected Transfer event");
+    Transfer::decode(&mut &event.data[..]).unwrap()
+}
+
+fn decode_approval(event: &EmittedEvent) -> Approval {
+    assert_eq!(event.topics.first().map(|t| t.as_slice()), Approval::SIGNATURE_TOPIC.as_ref().map(|t| t.as_slice()), "expected Approval event");
+    Approval::decode(&mut &event.data[..]).unwrap()
+}
+
+fn transfer_works<T: Token>(token: &mut T, owner: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+    let recipient_balance = token.balance_of(recipient);
+    let count = recorded_events().count();
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.transfer(recipient, 1, Vec::new()).unwrap();
+
+    assert_eq!(token.balance_of(owner), owner_balance - 1);
+    assert_eq!(token.balance_of(recipient), recipient_balance + 1);
+    let events = events_after(count);
+    assert_eq!(events.len(), 1, "transfer should emit a single event");
+    let transfer = decode_transfer(&events[0]);
+    assert_eq!((transfer.from, transfer.to, transfer.value), (Some(owner), Some(recipient), 1));
+
+    set_caller::<DefaultEnvironment>(recipient);
+    token.transfer(owner, 1, Vec::new()).unwrap();
+}
+
+fn transfer_noop_works<T: Token>(token: &mut T, owner: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+    let count = recorded_events().count();
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.transfer(owner, owner_balance + 1, Vec::new()).unwrap();
+    token.transfer(recipient, 0, Vec::new()).unwrap();
+
+    assert_eq!(token.balance_of(owner), owner_balance);
+    assert!(events_after(count).is_empty(), "no-op transfer should not emit events");
+}
+
+fn transfer_fails_on_insufficient_balance<T: Token>(token: &mut T, owner: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+
+    set_caller::<DefaultEnvironment>(owner);
+    let result = token.transfer(recipient, owner_balance + 1, Vec::new());
+    assert!(matches!(result, Err(PSP22Error::InsufficientBalance)), "transfer above balance should fail. Got: {:?}", result);
+}
+
+fn approve_works<T: Token>(token: &mut T, owner: AccountId, spender: AccountId) {
+    let count = recorded_events().count();
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(spender, 5).unwrap();
+
+    assert_eq!(token.allowance(owner, spender), 5);
+    let events = events_after(count);
+    assert_eq!(events.len(), 1, "approve should emit a single event");
+    let approval = decode_approval(&events[0]);
+    assert_eq!((approval.owner, approval.spender, approval.amount), (owner, spender, 5));
+
+    let count = recorded_events().count();
+    token.approve(owner, 5).unwrap();
+    assert_eq!(token.allowance(owner, owner), 0);
+    assert!(events_after(count).is_empty(), "approve to self should not emit events");
+
+    token.approve(spender, 0).unwrap();
+}
+
+fn transfer_from_spends_caller_allowance<T: Token>(token: &mut T, owner: AccountId, spender: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+    let recipient_balance = token.balance_of(recipient);
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(spender, 2).unwrap();
+    let count = recorded_events().count();
+
+    set_caller::<DefaultEnvironment>(spender);
+    token.transfer_from(owner, recipient, 1, Vec::new()).unwrap();
+
+    assert_eq!(token.balance_of(owner), owner_balance - 1);
+    assert_eq!(token.balance_of(recipient), recipient_balance + 1);
+    assert_eq!(token.allowance(owner, spender), 1);
+    assert_eq!(token.allowance(owner, recipient), 0);
+
+    let events = events_after(count);
+    assert_eq!(events.len(), 2, "transfer_from should emit Approval and Transfer");
+    let approval = decode_approval(&events[0]);
+    assert_eq!((approval.owner, approval.spender, approval.amount), (owner, spender, 1));
+    let transfer = decode_transfer(&events[1]);
+    assert_eq!((transfer.from, transfer.to, transfer.value), (Some(owner), Some(recipient), 1));
+
+    set_caller::<DefaultEnvironment>(recipient);
+    token.transfer(owner, 1, Vec::new()).unwrap();
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(spender, 0).unwrap();
+}
+
+fn transfer_from_fails_on_insufficient_allowance<T: Token>(token: &mut T, owner: AccountId, spender: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(recipient, 1).unwrap();
+
+    set_caller::<DefaultEnvironment>(spender);
+    let result = token.transfer_from(owner, recipient, 1, Vec::new());
+    assert!(matches!(result, Err(PSP22Error::InsufficientAllowance)), "transfer_from without allowance should fail. Got: {:?}", result);
+
+    let result = token.transfer_from(owner, recipient, owner_balance + 1, Vec::new());
+    assert!(matches!(result, Err(PSP22Error::InsufficientAllowance)), "insufficient allowance should take precedence. Got: {:?}", result);
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(recipient, 0).unwrap();
+}
+
+fn transfer_from_fails_on_insufficient_balance<T: Token>(token: &mut T, owner: AccountId, spender: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(spender, owner_balance + 1).unwrap();
+
+    set_caller::<DefaultEnvironment>(spender);
+    let result = token.transfer_from(owner, recipient, owner_balance + 1, Vec::new());
+    assert!(matches!(result, Err(PSP22Error::InsufficientBalance)), "transfer_from above balance should fail. Got: {:?}", result);
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(spender, 0).unwrap();
+}
+
+fn transfer_from_by_owner_needs_no_allowance<T: Token>(token: &mut T, owner: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+    let count = recorded_events().count();
+
+    set_caller::<DefaultEnvironment>(owner);
+    token.transfer_from(owner, recipient, 1, Vec::new()).unwrap();
+
+    assert_eq!(token.balance_of(owner), owner_balance - 1);
+    let events = events_after(count);
+    assert_eq!(events.len(), 1, "transfer_from by owner should emit only Transfer");
+    decode_transfer(&events[0]);
+
+    set_caller::<DefaultEnvironment>(recipient);
+    token.transfer(owner, 1, Vec::new()).unwrap();
+}
+
+fn transfer_from_noop_works<T: Token>(token: &mut T, owner: AccountId, spender: AccountId, recipient: AccountId) {
+    let owner_balance = token.balance_of(owner);
+    let count = recorded_events().count();
+
+    set_caller::<DefaultEnvironment>(spender);
+    token.transfer_from(owner, owner, owner_balance + 1, Vec::new()).unwrap();
+    token.transfer_from(owner, recipient, 0, Vec::new()).unwrap();
+
+    assert_eq!(token.balance_of(owner), owner_balance);
+    assert!(events_after(count).is_empty(), "no-op transfer_from should not emit events");
+}
+
+fn increase_allowance_works<T: Token>(token: &mut T, owner: AccountId, spender: AccountId) {
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(spender, 1).unwrap();
+    let count = recorded_events().count();
+
+    token.increase_allowance(spender, 2).unwrap();
+    assert_eq!(token.allowance(owner, spender), 3);
+    let events = events_after(count);
+    assert_eq!(events.len(), 1, "increase_allowance should emit a single event");
+    assert_eq!(decode_approval(&events[0]).amount, 3);
+
+    let count = recorded_events().count();
+    token.increase_allowance(spender, 0).unwrap();
+    token.increase_allowance(owner, 2).unwrap();
+    assert_eq!(token.allowance(owner, owner), 0);
+    assert!(events_after(count).is_empty(), "no-op increase_allowance should not emit events");
+
+    token.approve(spender, 0).unwrap();
+}
+
+fn decrease_allowance_works<T: Token>(token: &mut T, owner: AccountId, spender: AccountId) {
+    set_caller::<DefaultEnvironment>(owner);
+    token.approve(spender, 3).unwrap();
+    let count = recorded_events().count();
+
+    token.decrease_allowance(spender, 1).unwrap();
+    assert_eq!(token.allowance(owner, spender), 2);
+    let events = events_after(count);
+    assert_eq!(events.len(), 1, "decrease_allowance should emit a single event");
+    assert_eq!(decode_approval(&events[0]).amount, 2);
+
+    let result = token.decrease_allowance(spender, 3);
+    assert!(matches!(result, Err(PSP22Error::InsufficientAllowance)), "decrease_allowance below zero should fail. Got: {:?}", result);
+    assert_eq!(token.allowance(owner, spender), 2);
+
+    let count = recorded_events().count();
+    token.decrease_allowance(spender, 0).unwrap();
+    token.decrease_allowance(owner, 1).unwrap();
+    assert!(events_after(count).is_empty(), "no-op decrease_allowance should not emit events");
+
+    token.approve(spender, 0).unwrap();
+}